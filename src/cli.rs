@@ -0,0 +1,371 @@
+use systemd_manager::{Analyze, BusType, Error, JournalFormat, JournalQuery, LocalBackend, SystemdBackend, SystemdUnit};
+use systemd_manager::systemd::dbus;
+
+/// The command completed successfully.
+pub const EXIT_SUCCESS: i32 = 0;
+/// The command failed, or the unit is not in the requested state.
+pub const EXIT_FAILURE: i32 = 1;
+/// The command line could not be understood.
+pub const EXIT_USAGE: i32 = 2;
+/// The unit is not running, as with `systemctl status`.
+pub const EXIT_INACTIVE: i32 = 3;
+/// The unit does not exist, as with `systemctl status`.
+pub const EXIT_NO_SUCH_UNIT: i32 = 4;
+/// The bus could not be reached (`EX_UNAVAILABLE`).
+pub const EXIT_UNAVAILABLE: i32 = 69;
+/// The bus did not reply in time (`EX_TEMPFAIL`).
+pub const EXIT_TIMEOUT: i32 = 75;
+/// The user is not authorized to perform the command (`EX_NOPERM`).
+pub const EXIT_ACCESS_DENIED: i32 = 77;
+
+const USAGE: &str = "Usage: systemd-manager [OPTIONS] [COMMAND]
+
+Launches the graphical interface when no command is given.
+
+Commands:
+    list                   List the unit files and their states
+    status UNIT            Show the state of a unit
+    start UNIT...          Start the units
+    stop UNIT...           Stop the units
+    enable UNIT...         Enable the unit files
+    disable UNIT...        Disable the unit files
+    blame                  List the time that each unit took to initialize during boot
+    journal [UNIT...]      Show the journal of the current boot, or of the given units
+    tui                    Launch the terminal interface
+
+Options:
+    --system               Manage the system's units (default)
+    --user                 Manage the user's units
+    --json                 Print the output as JSON
+    -n, --lines N          Show the last N journal entries
+    -h, --help             Show this help
+
+Exit Codes:
+    0 on success, 1 on failure, 2 on invalid usage, 3 if the unit is inactive, 4 if the unit does not exist,
+    69 if the bus is unavailable, 75 if the bus timed out, and 77 if access was denied.";
+
+/// The command that was requested on the command line.
+#[derive(Debug, PartialEq)]
+enum Command {
+    List,
+    Status(String),
+    Start(Vec<String>),
+    Stop(Vec<String>),
+    Enable(Vec<String>),
+    Disable(Vec<String>),
+    Blame,
+    Journal(Vec<String>),
+    Tui,
+    Help,
+}
+
+/// The command, along with the options that it is run with.
+#[derive(Debug, PartialEq)]
+struct Options {
+    command: Command,
+    bustype: BusType,
+    json: bool,
+    lines: Option<u32>,
+}
+
+/// Runs the command that is described by the arguments, and returns the code that the process should exit with.
+pub fn run(args: &[String]) -> i32 {
+    let options = match parse_args(args) {
+        Ok(options) => options,
+        Err(why) => {
+            eprintln!("systemd-manager: {}\n\n{}", why, USAGE);
+            return EXIT_USAGE;
+        }
+    };
+
+    match options.command {
+        Command::Help => {
+            println!("{}", USAGE);
+            EXIT_SUCCESS
+        }
+        Command::List => list(&options),
+        Command::Status(ref name) => status(&options, name),
+        // Starting and stopping return once the job is queued, so the outcome is that of the unit once it has run.
+        Command::Start(ref names) => act(&options, names, "started", |backend, unit| {
+            backend.start(unit).and_then(|_| backend.reload_settled(unit)).and_then(|unit| unit.check_started())
+        }),
+        Command::Stop(ref names) => act(&options, names, "stopped", |backend, unit| {
+            backend.stop(unit).and_then(|_| backend.reload_settled(unit)).and_then(|unit| unit.check_stopped())
+        }),
+        Command::Enable(ref names) => act(&options, names, "enabled", |backend, unit| {
+            backend.enable(unit).map(|_| ())
+        }),
+        Command::Disable(ref names) => act(&options, names, "disabled", |backend, unit| {
+            backend.disable(unit).map(|_| ())
+        }),
+        Command::Blame => blame(&options),
+        Command::Journal(ref names) => journal(&options, names),
+        Command::Tui => tui(&options),
+    }
+}
+
+/// Parses the command line into the requested command and its options.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut bustype = BusType::System;
+    let mut json = false;
+    let mut lines = None;
+    let mut words = Vec::new();
+
+    let mut iterator = args.iter();
+    while let Some(arg) = iterator.next() {
+        match arg.as_str() {
+            "--system" => bustype = BusType::System,
+            "--user" => bustype = BusType::Session,
+            "--json" => json = true,
+            "-n" | "--lines" => {
+                let value = iterator.next().ok_or_else(|| format!("{} requires a number", arg))?;
+                lines = Some(value.parse::<u32>().map_err(|_| format!("'{}' is not a valid number", value))?);
+            }
+            "-h" | "--help" => words.insert(0, "help".to_owned()),
+            _ if arg.starts_with('-') => return Err(format!("unknown option '{}'", arg)),
+            _ => words.push(arg.clone()),
+        }
+    }
+
+    let (verb, units) = match words.split_first() {
+        Some((verb, units)) => (verb.as_str(), units.to_vec()),
+        None => ("help", Vec::new()),
+    };
+
+    // Commands that operate upon units require at least one unit.
+    let required = |units: Vec<String>| if units.is_empty() {
+        Err(format!("{} requires at least one unit", verb))
+    } else {
+        Ok(units)
+    };
+
+    let command = match verb {
+        "help" => Command::Help,
+        "list" => Command::List,
+        "status" if units.len() == 1 => Command::Status(units[0].clone()),
+        "status" => return Err(String::from("status requires exactly one unit")),
+        "start" => Command::Start(required(units)?),
+        "stop" => Command::Stop(required(units)?),
+        "enable" => Command::Enable(required(units)?),
+        "disable" => Command::Disable(required(units)?),
+        "blame" => Command::Blame,
+        "journal" => Command::Journal(units),
+        "tui" => Command::Tui,
+        _ => return Err(format!("unknown command '{}'", verb)),
+    };
+
+    Ok(Options { command: command, bustype: bustype, json: json, lines: lines })
+}
+
+/// Reports the error on the standard error, and returns the exit code that corresponds to it.
+fn fail(context: &str, error: &Error) -> i32 {
+    eprintln!("systemd-manager: {}: {}", context, error);
+    exit_code(error)
+}
+
+/// Obtains the exit code that corresponds to the error.
+fn exit_code(error: &Error) -> i32 {
+    match *error {
+        Error::AccessDenied(_) => EXIT_ACCESS_DENIED,
+        Error::NoSuchUnit(_) => EXIT_NO_SUCH_UNIT,
+        Error::BusUnavailable(_) => EXIT_UNAVAILABLE,
+        Error::Timeout => EXIT_TIMEOUT,
+        Error::JobFailed(_) | Error::Parse(_) => EXIT_FAILURE,
+    }
+}
+
+/// Lists every unit file of the bus, along with its enablement and runtime state.
+fn list(options: &Options) -> i32 {
+    let units = match dbus::list_unit_files(options.bustype) {
+        Ok(units) => units,
+        Err(why) => return fail("unable to list the units", &why),
+    };
+
+    if options.json {
+        println!("[{}]", units.iter().map(unit_json).collect::<Vec<String>>().join(","));
+    } else {
+        let width = units.iter().map(|unit| unit.name.len()).max().unwrap_or(0);
+        println!("{:width$}  {:10}  {:12}  {:10}  {}", "UNIT", "ENABLEMENT", "ACTIVE", "SUB", "DESCRIPTION",
+            width = width);
+        for unit in &units {
+            println!("{:width$}  {:10}  {:12}  {:10}  {}", unit.name, unit.state.as_str(),
+                unit.active_state.as_str(), unit.sub_state, unit.description, width = width);
+        }
+    }
+    EXIT_SUCCESS
+}
+
+/// Displays the current state of a unit.
+fn status(options: &Options, name: &str) -> i32 {
+    let unit = match dbus::load_unit(options.bustype, name) {
+        Ok(unit) => unit,
+        Err(why) => return fail(&format!("unable to obtain the status of {}", name), &why),
+    };
+
+    if options.json {
+        println!("{}", unit_json(&unit));
+    } else {
+        println!("{} - {}", unit.name, unit.description);
+        println!("    Path: {}", unit.path);
+        println!("    Enablement: {}", unit.state.as_str());
+        println!("    Active: {} ({})", unit.active_state.as_str(), unit.sub_state);
+    }
+
+    if unit.active_state == systemd_manager::ActiveState::Active { EXIT_SUCCESS } else { EXIT_INACTIVE }
+}
+
+/// Performs the action upon each of the units, and reports the outcome of each. Each unit is loaded by its name, so
+/// that the instances of templates and transient units may be acted upon. The exit code is that of the last action
+/// which failed.
+fn act<F>(options: &Options, names: &[String], action: &str, perform: F) -> i32
+    where F: Fn(&LocalBackend, &SystemdUnit) -> Result<(), Error>
+{
+    let backend = LocalBackend::new(options.bustype);
+    let mut code = EXIT_SUCCESS;
+    let mut results = Vec::new();
+    for name in names {
+        match dbus::load_unit(options.bustype, name).and_then(|unit| perform(&backend, &unit)) {
+            Ok(()) => {
+                if options.json {
+                    results.push(format!("{{\"unit\":{},\"result\":\"ok\"}}", json_string(name)));
+                } else {
+                    println!("{} {}", name, action);
+                }
+            }
+            Err(why) => {
+                if options.json {
+                    results.push(format!("{{\"unit\":{},\"result\":\"error\",\"error\":{}}}", json_string(name),
+                        json_string(&why.to_string())));
+                    code = exit_code(&why);
+                } else {
+                    code = fail(&format!("{} could not be {}", name, action), &why);
+                }
+            }
+        }
+    }
+
+    if options.json {
+        println!("[{}]", results.join(","));
+    }
+    code
+}
+
+/// Lists the time that each unit took to initialize during boot, slowest first.
+fn blame(options: &Options) -> i32 {
+    let mut units: Vec<Analyze> = match Analyze::blame() {
+        Ok(units) => units,
+        Err(why) => return fail("unable to run systemd-analyze blame", &why),
+    };
+    units.reverse();
+
+    if options.json {
+        let units: Vec<String> = units.iter()
+            .map(|unit| format!("{{\"unit\":{},\"time_ms\":{}}}", json_string(&unit.service), unit.time))
+            .collect();
+        println!("[{}]", units.join(","));
+    } else {
+        for unit in &units {
+            println!("{:>8}ms  {}", unit.time, unit.service);
+        }
+    }
+    EXIT_SUCCESS
+}
+
+/// Displays the journal of the current boot, or the merged journal of the given units, oldest entries first.
+/// The JSON output contains one JSON object per entry, as written by `journalctl -o json`.
+fn journal(options: &Options, names: &[String]) -> i32 {
+    let mut query = if names.is_empty() {
+        JournalQuery::boot(options.bustype)
+    } else {
+        JournalQuery::units(names.to_vec(), options.bustype)
+    };
+    query.reverse = false;
+    query.lines = options.lines;
+
+    let format = if options.json { JournalFormat::Json } else { JournalFormat::Text };
+    match query.read_as(format) {
+        Ok(entries) => {
+            print!("{}", entries);
+            EXIT_SUCCESS
+        }
+        Err(why) => fail("unable to read the journal", &why),
+    }
+}
+
+/// Runs the terminal interface, if this build includes it.
+#[cfg(feature = "tui")]
+fn tui(options: &Options) -> i32 {
+    match crate::gui::tui::launch(options.bustype) {
+        Ok(()) => EXIT_SUCCESS,
+        Err(why) => {
+            eprintln!("systemd-manager: unable to run the terminal interface: {}", why);
+            EXIT_FAILURE
+        }
+    }
+}
+
+#[cfg(not(feature = "tui"))]
+fn tui(_: &Options) -> i32 {
+    eprintln!("systemd-manager: this build does not include the terminal interface, as it was built without the \
+               `tui` feature.");
+    EXIT_USAGE
+}
+
+/// Formats the unit as a JSON object.
+fn unit_json(unit: &SystemdUnit) -> String {
+    format!("{{\"name\":{},\"path\":{},\"description\":{},\"type\":{},\"state\":{},\"active_state\":{},\
+             \"sub_state\":{}}}",
+        json_string(&unit.name), json_string(&unit.path), json_string(&unit.description),
+        json_string(unit.utype.as_str()), json_string(unit.state.as_str()),
+        json_string(unit.active_state.as_str()), json_string(&unit.sub_state))
+}
+
+/// Quotes the string as a JSON string, escaping the characters that JSON does not allow within strings.
+fn json_string(input: &str) -> String {
+    let mut output = String::with_capacity(input.len() + 2);
+    output.push('"');
+    for character in input.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c),
+        }
+    }
+    output.push('"');
+    output
+}
+
+#[test]
+fn test_parse_args() {
+    let args = |line: &str| line.split_whitespace().map(String::from).collect::<Vec<String>>();
+    assert_eq!(parse_args(&args("--user --json start sshd.service cups.socket")), Ok(Options {
+        command: Command::Start(vec![String::from("sshd.service"), String::from("cups.socket")]),
+        bustype: BusType::Session,
+        json: true,
+        lines: None,
+    }));
+    assert_eq!(parse_args(&args("journal -n 20")).map(|options| options.lines), Ok(Some(20)));
+    assert_eq!(parse_args(&args("list --help")).map(|options| options.command), Ok(Command::Help));
+    assert_eq!(parse_args(&args("--user tui")).map(|options| options.command), Ok(Command::Tui));
+    assert!(parse_args(&args("stop")).is_err());
+    assert!(parse_args(&args("status a.service b.service")).is_err());
+    assert!(parse_args(&args("frobnicate")).is_err());
+    assert!(parse_args(&args("list --verbose")).is_err());
+}
+
+#[test]
+fn test_json_string() {
+    assert_eq!(json_string("OpenSSH \"Daemon\""), "\"OpenSSH \\\"Daemon\\\"\"");
+    assert_eq!(json_string("a\\b\nc\u{1}"), "\"a\\\\b\\nc\\u0001\"");
+}
+
+#[test]
+fn test_exit_code() {
+    assert_eq!(exit_code(&Error::NoSuchUnit(String::from("example.service"))), EXIT_NO_SUCH_UNIT);
+    assert_eq!(exit_code(&Error::AccessDenied(String::new())), EXIT_ACCESS_DENIED);
+    assert_eq!(exit_code(&Error::Timeout), EXIT_TIMEOUT);
+}
@@ -0,0 +1,199 @@
+use std::rc::Rc;
+use gtk::*;
+use crate::systemd::backend::SystemdBackend;
+use crate::systemd::{Error, SystemdUnit};
+use super::notify::{self, Notifier};
+use super::store::UnitStore;
+use super::worker::{self, Busy};
+
+/// The actions that may be performed upon every unit that is selected in a unit list, or upon a single unit through
+/// its context menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BatchAction {
+    Start,
+    Stop,
+    Restart,
+    Reload,
+    Enable,
+    Disable,
+    Mask,
+}
+
+impl BatchAction {
+    /// The name of the action, as it is displayed on its button.
+    pub fn label(&self) -> &'static str {
+        match *self {
+            BatchAction::Start => "Start",
+            BatchAction::Stop => "Stop",
+            BatchAction::Restart => "Restart",
+            BatchAction::Reload => "Reload",
+            BatchAction::Enable => "Enable",
+            BatchAction::Disable => "Disable",
+            BatchAction::Mask => "Mask",
+        }
+    }
+
+    fn past_tense(&self) -> &'static str {
+        match *self {
+            BatchAction::Start => "started",
+            BatchAction::Stop => "stopped",
+            BatchAction::Restart => "restarted",
+            BatchAction::Reload => "reloaded",
+            BatchAction::Enable => "enabled",
+            BatchAction::Disable => "disabled",
+            BatchAction::Mask => "masked",
+        }
+    }
+
+    /// Performs the action upon the unit, returning true if the unit file was already in the requested state.
+    fn perform(&self, backend: &dyn SystemdBackend, unit: &SystemdUnit) -> Result<bool, Error> {
+        match *self {
+            BatchAction::Start => backend.start(unit).map(|_| false),
+            BatchAction::Stop => backend.stop(unit).map(|_| false),
+            BatchAction::Restart => backend.restart(unit).map(|_| false),
+            BatchAction::Reload => backend.reload(unit).map(|_| false),
+            BatchAction::Enable => backend.enable(unit),
+            BatchAction::Disable => backend.disable(unit),
+            BatchAction::Mask => backend.mask(unit),
+        }
+    }
+
+    /// Performs the action upon the unit and collects its state once the queued job has finished. A unit that
+    /// failed once it was started or restarted is reported as a failure, as is a unit that is still running once it
+    /// was stopped. The unit is returned as it was if it could not be reloaded.
+    fn perform_settled(&self, backend: &dyn SystemdBackend, unit: &SystemdUnit) -> (Result<bool, Error>, SystemdUnit) {
+        let result = self.perform(backend, unit);
+        let reloaded = match *self {
+            BatchAction::Start | BatchAction::Stop | BatchAction::Restart | BatchAction::Reload if result.is_ok() => {
+                backend.reload_settled(unit)
+            }
+            _ => backend.reload_unit(unit),
+        };
+        let reloaded = match reloaded {
+            Ok(reloaded) => reloaded,
+            Err(_) => return (result, unit.clone()),
+        };
+        let result = result.and_then(|already| match *self {
+            BatchAction::Start | BatchAction::Restart => reloaded.check_started().map(|_| already),
+            BatchAction::Stop => reloaded.check_stopped().map(|_| already),
+            _ => Ok(already),
+        });
+        (result, reloaded)
+    }
+}
+
+/// Asks for confirmation before performing the action upon the units, listing each of the units that are affected.
+fn confirm(window: &Window, action: BatchAction, units: &[SystemdUnit]) -> bool {
+    let dialog = Dialog::new_with_buttons(Some(&format!("{} Units", action.label())), Some(window),
+        DIALOG_MODAL | DIALOG_DESTROY_WITH_PARENT,
+        &[("Cancel", ResponseType::Cancel.into()), (action.label(), ResponseType::Accept.into())]);
+    dialog.set_default_size(400, 300);
+
+    let question = Label::new(Some(&format!("{} the following {} units?", action.label(), units.len())[..]));
+    question.set_xalign(0.0);
+    let names: Vec<&str> = units.iter().map(|unit| unit.name.as_str()).collect();
+    let view = TextView::new();
+    view.set_editable(false);
+    view.set_monospace(true);
+    view.get_buffer().map(|buffer| buffer.set_text(&names.join("\n")));
+    let scrolled = ScrolledWindow::new(None, None);
+    scrolled.set_shadow_type(ShadowType::In);
+    scrolled.add(&view);
+    let content = dialog.get_content_area();
+    content.set_spacing(5);
+    content.pack_start(&question, false, false, 0);
+    content.pack_start(&scrolled, true, true, 0);
+    content.show_all();
+
+    let response = dialog.run();
+    dialog.destroy();
+    response == ResponseType::Accept.into()
+}
+
+/// Describes the outcome of the action upon each unit, one unit per line.
+fn summarize(action: BatchAction, outcomes: &[(String, Result<bool, Error>)]) -> String {
+    outcomes.iter()
+        .map(|&(ref name, ref result)| match *result {
+            Ok(true) => format!("{}: was already {}", name, action.past_tense()),
+            Ok(false) => format!("{}: {}", name, action.past_tense()),
+            Err(ref why) => format!("{}: could not be {}: {}", name, action.past_tense(), why),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Performs the action upon every unit in the background, once it has been confirmed. Each unit is reloaded once its
+/// job has finished so that the store reflects the outcome, and the outcome of each unit is displayed in a summary
+/// before `finished` is called.
+pub fn run<F: FnOnce() + 'static>(window: &Window, action: BatchAction, units: Vec<SystemdUnit>, store: &UnitStore,
+        notifier: &Notifier, busy: &Busy, finished: F) {
+    if units.is_empty() || !confirm(window, action, &units) {
+        return
+    }
+
+    let operation = busy.begin();
+    let (window, store, notifier) = (window.clone(), store.clone(), notifier.clone());
+    let (backend, generation) = (store.backend(), store.generation());
+    let work = move || {
+        units.iter().map(|unit| action.perform_settled(&*backend, unit)).collect::<Vec<_>>()
+    };
+
+    worker::spawn(work, move |performed| {
+        operation.end();
+        let mut outcomes = Vec::with_capacity(performed.len());
+        for (result, reloaded) in performed {
+            outcomes.push((reloaded.name.clone(), result));
+            store.update_current(generation, reloaded);
+        }
+
+        let summary = summarize(action, &outcomes);
+        let failed = outcomes.iter().filter(|&&(_, ref result)| result.is_err()).count();
+        if failed == 0 {
+            notifier.success(format!("{} units have been {}", outcomes.len(), action.past_tense()));
+        } else {
+            notifier.failure(format!("{} of {} units could not be {}", failed, outcomes.len(), action.past_tense()),
+                summary.clone());
+        }
+        notify::show_text(&window, &format!("{} Results", action.label()), &summary);
+        finished();
+    });
+}
+
+/// Performs the action upon a single unit in the background without asking for confirmation, reporting the outcome
+/// through the notifier. The unit is reloaded once its job has finished so that the store reflects the outcome, and
+/// `finished` is called once the store has been updated, including after the action is retried.
+pub fn perform(action: BatchAction, unit: &SystemdUnit, store: &UnitStore, notifier: &Notifier, busy: &Busy,
+        finished: Rc<dyn Fn()>) {
+    let operation = busy.begin();
+    let (unit, store, notifier, busy) = (unit.clone(), store.clone(), notifier.clone(), busy.clone());
+    let (backend, generation, background_unit) = (store.backend(), store.generation(), unit.clone());
+    let work = move || action.perform_settled(&*backend, &background_unit);
+    worker::spawn(work, move |(result, reloaded)| {
+        operation.end();
+        store.update_current(generation, reloaded);
+        match result {
+            Ok(true) => notifier.success(format!("{} was already {}", unit.name, action.past_tense())),
+            Ok(false) => notifier.success(format!("{} has been {}", unit.name, action.past_tense())),
+            Err(why) => {
+                let (retry_notifier, retry_finished) = (notifier.clone(), finished.clone());
+                let message = format!("{} could not be {}", unit.name, action.past_tense());
+                notifier.failure_with_retry(message, why.to_string(), move || {
+                    perform(action, &unit, &store, &retry_notifier, &busy, retry_finished.clone())
+                });
+            }
+        }
+        finished();
+    });
+}
+
+#[test]
+fn test_summarize() {
+    let outcomes = [
+        (String::from("worker@1.service"), Ok(false)),
+        (String::from("worker@2.service"), Ok(true)),
+        (String::from("worker@3.service"), Err(Error::AccessDenied(String::from("Permission denied")))),
+    ];
+    assert_eq!(summarize(BatchAction::Enable, &outcomes), "worker@1.service: enabled\n\
+        worker@2.service: was already enabled\n\
+        worker@3.service: could not be enabled: access denied: Permission denied");
+}
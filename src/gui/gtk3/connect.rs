@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+use gtk::*;
+use crate::systemd::backend::{AddressBackend, SystemdBackend};
+use crate::systemd::dbus::dbus::BusType;
+use crate::systemd::logind::{self, LoggedInUser, UserBackend};
+use super::worker;
+
+/// Asks for the manager to connect to, which is either a D-Bus address, such as a socket that was forwarded over
+/// SSH, an SSH host, or the user manager of a logged-in user. Returns the backend of the chosen manager, or `None`
+/// if the dialog was cancelled.
+pub fn run(window: &Window) -> Option<Arc<dyn SystemdBackend>> {
+    let dialog = Dialog::new_with_buttons(Some("Connect to…"), Some(window),
+        DIALOG_MODAL | DIALOG_DESTROY_WITH_PARENT,
+        &[("Cancel", ResponseType::Cancel.into()), ("Connect", ResponseType::Accept.into())]);
+    dialog.set_default_response(ResponseType::Accept.into());
+
+    let hint = Label::new(Some("Enter the D-Bus address of a systemd manager, such as \
+        unix:path=/run/systemd/private, or the SSH host to reach it through, such as root@server. \
+        Alternatively, choose a logged-in user to manage their user manager."));
+    hint.set_line_wrap(true);
+    hint.set_max_width_chars(50);
+
+    let target = Entry::new();
+    target.set_activates_default(true);
+
+    // The manager at the address is the system's manager, unless a user's manager is chosen.
+    let manager = ComboBoxText::new();
+    manager.append(Some("system"), "System Manager");
+    manager.append(Some("user"), "User Manager");
+    manager.set_active_id(Some("system"));
+
+    // The logged-in users are listed in the background, and choosing one takes the place of the address or host.
+    let user = ComboBoxText::new();
+    user.append(Some("address"), "Address or Host");
+    user.set_active_id(Some("address"));
+    let users = Rc::new(RefCell::new(Vec::new()));
+    {
+        let (user, users) = (user.clone(), users.clone());
+        worker::spawn(logind::list_users, move |listed| {
+            for logged_in in listed.as_ref().map(|listed| listed.as_slice()).unwrap_or(&[]) {
+                user.append(Some(&logged_in.uid.to_string()), &format!("User Manager of {}", logged_in.name));
+            }
+            *users.borrow_mut() = listed.unwrap_or_default();
+        });
+    }
+    {
+        let (target, manager) = (target.clone(), manager.clone());
+        user.connect_changed(move |user| {
+            let address = user.get_active_id().map_or(true, |id| id == "address");
+            target.set_sensitive(address);
+            manager.set_sensitive(address);
+        });
+    }
+
+    let content = dialog.get_content_area();
+    content.set_spacing(5);
+    content.pack_start(&hint, false, false, 5);
+    content.pack_start(&target, false, false, 0);
+    content.pack_start(&manager, false, false, 0);
+    content.pack_start(&user, false, false, 0);
+    content.show_all();
+
+    let backend = if dialog.run() == ResponseType::Accept.into() {
+        match user.get_active_id().and_then(|id| get_user(&users.borrow(), &id)) {
+            Some(chosen) => Some(Arc::new(UserBackend::new(&chosen)) as Arc<dyn SystemdBackend>),
+            None => {
+                let bustype = match manager.get_active_id().as_ref().map(|id| id.as_str()) {
+                    Some("user") => BusType::Session,
+                    _ => BusType::System,
+                };
+                get_backend(target.get_text().unwrap_or_default().trim(), bustype)
+            }
+        }
+    } else {
+        None
+    };
+
+    dialog.destroy();
+    backend
+}
+
+/// Finds the logged-in user whose user ID is the ID of the chosen entry.
+fn get_user(users: &[LoggedInUser], id: &str) -> Option<LoggedInUser> {
+    users.iter().find(|user| user.uid.to_string() == id).cloned()
+}
+
+/// Creates the backend for the entered target, which is a D-Bus address if it names a transport, such as
+/// `unix:path=`, or otherwise an SSH host.
+fn get_backend(target: &str, bustype: BusType) -> Option<Arc<dyn SystemdBackend>> {
+    if target.is_empty() {
+        None
+    } else if target.contains(':') && target.contains('=') {
+        Some(Arc::new(AddressBackend::new(target, bustype)))
+    } else {
+        Some(Arc::new(AddressBackend::ssh(target, bustype)))
+    }
+}
+
+#[test]
+fn test_get_backend() {
+    let name = |target: &str| get_backend(target, BusType::System).map(|backend| backend.name());
+    assert_eq!(name("unix:path=/run/systemd/private"), Some(String::from("the bus at unix:path=/run/systemd/private")));
+    assert_eq!(name("root@server"), Some(String::from("root@server")));
+    assert_eq!(name(""), None);
+}
+
+#[test]
+fn test_get_user() {
+    let users = [LoggedInUser { uid: 1000, name: String::from("alice") }];
+    assert_eq!(get_user(&users, "1000").map(|user| user.name), Some(String::from("alice")));
+    assert_eq!(get_user(&users, "address"), None);
+}
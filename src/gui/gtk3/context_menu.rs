@@ -0,0 +1,171 @@
+use std::rc::Rc;
+use gdk;
+use gtk::*;
+use crate::systemd::{ActiveState, SystemdUnit, UnitState};
+use super::batch::BatchAction;
+use super::store::UnitStore;
+use super::units::{column, UnitLists};
+
+/// The entries of a unit's context menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MenuAction {
+    Control(BatchAction),
+    Edit,
+    ViewJournal,
+    CopyName,
+    CopyPath,
+}
+
+impl MenuAction {
+    fn label(&self) -> &'static str {
+        match *self {
+            MenuAction::Control(action) => action.label(),
+            MenuAction::Edit => "Edit",
+            MenuAction::ViewJournal => "View Journal",
+            MenuAction::CopyName => "Copy Name",
+            MenuAction::CopyPath => "Copy Path",
+        }
+    }
+}
+
+/// Lists the actions that make sense for the unit's current state, in groups that are separated in the menu. A
+/// running unit may be stopped but not started, a masked unit can neither be started nor enabled, a unit that systemd
+/// reports as unable to reload cannot be reloaded, and a unit without a unit file has no path to edit or copy.
+fn available_actions(unit: &SystemdUnit) -> Vec<Vec<MenuAction>> {
+    let masked = unit.state == UnitState::Masked;
+    let running = match unit.active_state {
+        ActiveState::Active | ActiveState::Reloading | ActiveState::Activating => true,
+        _ => false,
+    };
+
+    let mut control = Vec::new();
+    if !running && !masked {
+        control.push(BatchAction::Start);
+    }
+    if running {
+        control.push(BatchAction::Stop);
+    }
+    if !masked {
+        control.push(BatchAction::Restart);
+    }
+    if unit.active_state == ActiveState::Active && unit.can_reload != Some(false) {
+        control.push(BatchAction::Reload);
+    }
+
+    let mut enablement = Vec::new();
+    match unit.state {
+        UnitState::Disabled | UnitState::Linked => enablement.push(BatchAction::Enable),
+        UnitState::Enabled | UnitState::Indirect => enablement.push(BatchAction::Disable),
+        _ => (),
+    }
+    if !masked {
+        enablement.push(BatchAction::Mask);
+    }
+
+    let has_file = !unit.path.is_empty();
+    let mut view = Vec::new();
+    if has_file {
+        view.push(MenuAction::Edit);
+    }
+    view.push(MenuAction::ViewJournal);
+    view.push(MenuAction::CopyName);
+    if has_file {
+        view.push(MenuAction::CopyPath);
+    }
+
+    vec![
+        control.into_iter().map(MenuAction::Control).collect(),
+        enablement.into_iter().map(MenuAction::Control).collect(),
+        view,
+    ].into_iter().filter(|group| !group.is_empty()).collect()
+}
+
+/// Displays the context menu of the unit, which hands the chosen action to `activate`.
+fn popup(unit: &SystemdUnit, activate: &Rc<dyn Fn(MenuAction, &SystemdUnit)>, button: u32, time: u32) {
+    let menu = Menu::new();
+    for (index, group) in available_actions(unit).iter().enumerate() {
+        if index > 0 {
+            menu.append(&SeparatorMenuItem::new());
+        }
+        for &action in group {
+            let item = MenuItem::new_with_label(action.label());
+            let (unit, activate) = (unit.clone(), activate.clone());
+            item.connect_activate(move |_| activate(action, &unit));
+            menu.append(&item);
+        }
+    }
+    menu.show_all();
+    menu.popup_easy(button, time);
+}
+
+/// Opens the context menu of a unit when its row is right-clicked, or when the menu key is pressed upon the selected
+/// row. The right-clicked row replaces the selection, so that the details pane displays the unit that the menu acts
+/// upon.
+pub fn connect(list: &TreeView, store: &UnitStore, activate: Rc<dyn Fn(MenuAction, &SystemdUnit)>) {
+    {
+        let (store, activate) = (store.clone(), activate.clone());
+        list.connect_button_press_event(move |list, event| {
+            if event.get_event_type() != gdk::EventType::ButtonPress || event.get_button() != 3 {
+                return Inhibit(false)
+            }
+
+            let (x, y) = event.get_position();
+            let path = match list.get_path_at_pos(x as i32, y as i32) {
+                Some((Some(path), _, _, _)) => path,
+                _ => return Inhibit(false),
+            };
+            // The menu acts upon the clicked unit alone, so a multiple selection is reduced to its row.
+            let selection = list.get_selection();
+            if selection.count_selected_rows() != 1 || !selection.path_is_selected(&path) {
+                selection.unselect_all();
+                selection.select_path(&path);
+            }
+
+            let unit = list.get_model()
+                .and_then(|model| model.get_iter(&path).map(|iter| (model, iter)))
+                .and_then(|(model, iter)| model.get_value(&iter, column::KEY as i32).get::<String>())
+                .and_then(|key| store.get(&key));
+            if let Some(unit) = unit {
+                popup(&unit, &activate, event.get_button(), event.get_time());
+            }
+            // The event is consumed so that the default handler does not change the selection again.
+            Inhibit(true)
+        });
+    }
+
+    let store = store.clone();
+    list.connect_popup_menu(move |list| {
+        match UnitLists::selected_in(list, &store) {
+            Some(unit) => {
+                popup(&unit, &activate, 0, get_current_event_time());
+                true
+            }
+            None => false,
+        }
+    });
+}
+
+/// Places the text on the clipboard.
+pub fn copy(text: &str) {
+    Clipboard::get(&gdk::Atom::intern("CLIPBOARD")).set_text(text);
+}
+
+#[test]
+fn test_available_actions() {
+    let mut unit = SystemdUnit::loaded("sshd.service", "OpenSSH Daemon", UnitState::Enabled, ActiveState::Active);
+    assert_eq!(available_actions(&unit), vec![
+        vec![MenuAction::Control(BatchAction::Stop), MenuAction::Control(BatchAction::Restart),
+            MenuAction::Control(BatchAction::Reload)],
+        vec![MenuAction::Control(BatchAction::Disable), MenuAction::Control(BatchAction::Mask)],
+        vec![MenuAction::Edit, MenuAction::ViewJournal, MenuAction::CopyName, MenuAction::CopyPath],
+    ]);
+
+    unit.can_reload = Some(false);
+    assert_eq!(available_actions(&unit)[0],
+        vec![MenuAction::Control(BatchAction::Stop), MenuAction::Control(BatchAction::Restart)]);
+
+    unit.state = UnitState::Masked;
+    unit.active_state = ActiveState::Failed;
+    unit.path = String::new();
+    assert_eq!(available_actions(&unit), vec![vec![MenuAction::ViewJournal, MenuAction::CopyName]]);
+}
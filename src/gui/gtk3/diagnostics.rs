@@ -0,0 +1,35 @@
+use gtk::*;
+use crate::systemd::diagnostics::Diagnostics;
+
+/// Displays a dialog explaining why the unit failed to start, with the unit's result, exit status, failed
+/// conditions and asserts, and the last lines of the journal for the failed invocation.
+pub fn show(window: &Window, name: &str, error: &str, diagnostics: &Diagnostics) {
+    let dialog = Dialog::new_with_buttons(Some(&format!("{} Failed to Start", name)), Some(window),
+        DIALOG_MODAL | DIALOG_DESTROY_WITH_PARENT, &[("Close", ResponseType::Close.into())]);
+    dialog.set_default_size(700, 500);
+
+    // The error that D-Bus replied with is shown above the report.
+    let error_label = Label::new(Some(error));
+    error_label.set_line_wrap(true);
+    error_label.set_selectable(true);
+
+    // The report is displayed in a monospaced `TextView`, so that the journal lines align as they would in a terminal.
+    let report = TextView::new();
+    report.set_editable(false);
+    report.set_monospace(true);
+    report.set_left_margin(5);
+    report.set_right_margin(5);
+    report.get_buffer().map(|buffer| buffer.set_text(diagnostics.summary().as_str()));
+    let scrolled = ScrolledWindow::new(None, None);
+    scrolled.set_shadow_type(ShadowType::In);
+    scrolled.add(&report);
+
+    let content = dialog.get_content_area();
+    content.set_spacing(5);
+    content.pack_start(&error_label, false, false, 5);
+    content.pack_start(&scrolled, true, true, 0);
+    content.show_all();
+
+    dialog.run();
+    dialog.destroy();
+}
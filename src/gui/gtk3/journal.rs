@@ -0,0 +1,200 @@
+use gtk::*;
+use crate::systemd::dbus::dbus::BusType;
+use crate::systemd::journal::{self, JournalFormat, JournalMaintenance, JournalQuery};
+use super::notify::Notifier;
+use super::store::UnitStore;
+use super::worker::{self, Busy};
+
+/// Programs the Systemd Journal `gtk::Stack`, which shows the journal of the whole boot, the kernel messages, or the
+/// merged entries of a chosen set of units.
+pub fn setup(builder: &Builder, notifier: &Notifier, store: &UnitStore, busy: &Busy) {
+    let journal_view: TextView = builder.get_object("system_journal_view").unwrap();
+    let refresh: Button = builder.get_object("system_journal_refresh").unwrap();
+    let export_button: Button = builder.get_object("system_journal_export").unwrap();
+    let source: ComboBoxText = builder.get_object("journal_source").unwrap();
+    let units_entry: Entry = builder.get_object("journal_units_entry").unwrap();
+    let session_switch: Switch = builder.get_object("session_switch").unwrap();
+    let main_window: Window = builder.get_object("main_window").unwrap();
+
+    {
+        // NOTE: Reload the journal entries for the selected source.
+        let source = source.clone();
+        let units_entry = units_entry.clone();
+        let session_switch = session_switch.clone();
+        let store = store.clone();
+        refresh.connect_clicked(move |_| {
+            let query = get_query(&source, &units_entry, &session_switch);
+            let journal_view = journal_view.clone();
+            let backend = store.backend();
+            worker::spawn(move || backend.journal(&query), move |entries| {
+                let entries = entries.unwrap_or_else(|why| format!("Unable to read the journal: {}", why));
+                journal_view.get_buffer().map(|buffer| buffer.set_text(entries.as_str()));
+            });
+        });
+    }
+
+    {
+        // NOTE: Only display the units entry when the entries of selected units are to be shown.
+        let units_entry = units_entry.clone();
+        let refresh = refresh.clone();
+        source.connect_changed(move |source| {
+            units_entry.set_visible(source.get_active_id().map_or(false, |id| id == "units"));
+            refresh.clicked();
+        });
+    }
+
+    {
+        // NOTE: Reload the journal once the list of units has been entered.
+        let refresh = refresh.clone();
+        units_entry.connect_activate(move |_| refresh.clicked());
+    }
+
+    {
+        // NOTE: Export the entries that are currently being displayed.
+        let source = source.clone();
+        let units_entry = units_entry.clone();
+        let notifier = notifier.clone();
+        let store = store.clone();
+        let busy = busy.clone();
+        export_button.connect_clicked(move |_| {
+            // The entries are exported as the manager's backend reads them, which may not be able to export them.
+            let name = format!("journal-{}", source.get_active_id().unwrap_or_default());
+            match store.backend().scope_journal(&get_query(&source, &units_entry, &session_switch)) {
+                Ok(query) => export(&main_window, &query, &name, &notifier, &busy),
+                Err(why) => notifier.failure(String::from("unable to export the journal"), why.to_string()),
+            }
+        });
+    }
+
+    setup_maintenance(builder, notifier);
+}
+
+/// Programs the maintenance page, which lists the disk usage of each journal directory and offers to vacuum and
+/// rotate the journal files.
+fn setup_maintenance(builder: &Builder, notifier: &Notifier) {
+    let usage_tree: TreeView = builder.get_object("journal_usage_tree").unwrap();
+    let usage_refresh: Button = builder.get_object("journal_usage_refresh").unwrap();
+    let vacuum_size_entry: Entry = builder.get_object("vacuum_size_entry").unwrap();
+    let vacuum_size_button: Button = builder.get_object("vacuum_size_button").unwrap();
+    let vacuum_time_entry: Entry = builder.get_object("vacuum_time_entry").unwrap();
+    let vacuum_time_button: Button = builder.get_object("vacuum_time_button").unwrap();
+    let rotate_button: Button = builder.get_object("journal_rotate_button").unwrap();
+
+    // The `ListStore` contains the directory and its disk usage.
+    let usage_store = ListStore::new(&[Type::String, Type::String]);
+    for &(title, id) in &[("Directory", 0), ("Disk Usage", 1)] {
+        let column = TreeViewColumn::new();
+        let renderer = CellRendererText::new();
+        column.set_title(title);
+        column.set_resizable(true);
+        column.pack_start(&renderer, true);
+        column.add_attribute(&renderer, "text", id);
+        usage_tree.append_column(&column);
+    }
+    usage_tree.set_model(Some(&usage_store));
+
+    {
+        // NOTE: Collect the disk usage of each journal directory.
+        let usage_store = usage_store.clone();
+        usage_refresh.connect_clicked(move |_| {
+            let usage_store = usage_store.clone();
+            worker::spawn(journal::disk_usage, move |usages| {
+                usage_store.clear();
+                for usage in usages.unwrap_or_default() {
+                    usage_store.insert_with_values(None, &[0, 1], &[&usage.directory, &usage.usage]);
+                }
+            });
+        });
+    }
+
+    // A simple macro for programming a button that performs a maintenance action.
+    macro_rules! maintenance_clicked {
+        ($button:ident, $action:expr) => {{
+            let notifier = notifier.clone();
+            let usage_refresh = usage_refresh.clone();
+            $button.connect_clicked(move |button| {
+                let action = $action;
+                let description = action.description();
+                let (button, notifier, usage_refresh) = (button.clone(), notifier.clone(), usage_refresh.clone());
+                // The button remains insensitive until the action, and its authorization prompt, has completed.
+                button.set_sensitive(false);
+                worker::spawn(move || action.run(), move |result| {
+                    button.set_sensitive(true);
+                    match result {
+                        Ok(()) => notifier.success(format!("{} succeeded", description)),
+                        Err(why) => notifier.failure(format!("{} failed", description), why.to_string()),
+                    }
+                    usage_refresh.clicked();
+                });
+            });
+        }}
+    }
+
+    maintenance_clicked!(vacuum_size_button,
+        JournalMaintenance::VacuumSize(vacuum_size_entry.get_text().unwrap_or_default()));
+    maintenance_clicked!(vacuum_time_button,
+        JournalMaintenance::VacuumTime(vacuum_time_entry.get_text().unwrap_or_default()));
+    maintenance_clicked!(rotate_button, JournalMaintenance::Rotate);
+
+    usage_refresh.clicked();
+}
+
+/// Creates the query that matches the journal source which is currently selected in the header bar.
+fn get_query(source: &ComboBoxText, units_entry: &Entry, session_switch: &Switch) -> JournalQuery {
+    let bustype = if session_switch.get_active() { BusType::Session } else { BusType::System };
+    match source.get_active_id().as_ref().map(|id| id.as_str()) {
+        Some("kernel") => JournalQuery::kernel(),
+        Some("units") => {
+            let units = units_entry.get_text().unwrap_or_default();
+            JournalQuery::units(units.split_whitespace().map(String::from).collect(), bustype)
+        }
+        _ => JournalQuery::boot(bustype),
+    }
+}
+
+/// Opens a save dialog and writes the entries matched by the query to the chosen file, in the chosen format, in the
+/// background.
+pub fn export(window: &Window, query: &JournalQuery, name: &str, notifier: &Notifier, busy: &Busy) {
+    let dialog = FileChooserDialog::new(Some("Export Journal"), Some(window), FileChooserAction::Save);
+    dialog.add_button("Cancel", ResponseType::Cancel.into());
+    dialog.add_button("Export", ResponseType::Accept.into());
+    dialog.set_do_overwrite_confirmation(true);
+    dialog.set_current_name(&format!("{}.{}", name, JournalFormat::Text.extension()));
+
+    // The format is chosen from a combo box beneath the file chooser.
+    let formats = ComboBoxText::new();
+    formats.append(Some("text"), "Plain Text");
+    formats.append(Some("json"), "JSON Lines");
+    formats.append(Some("export"), "Journal Export Format");
+    formats.set_active_id(Some("text"));
+    dialog.set_extra_widget(&formats);
+
+    {
+        // Keep the suggested file extension in line with the selected format.
+        let dialog = dialog.clone();
+        let name = name.to_owned();
+        formats.connect_changed(move |formats| {
+            let format = JournalFormat::from_id(&formats.get_active_id().unwrap_or_default());
+            dialog.set_current_name(&format!("{}.{}", name, format.extension()));
+        });
+    }
+
+    if dialog.run() == ResponseType::Accept.into() {
+        if let Some(path) = dialog.get_filename() {
+            let format = JournalFormat::from_id(&formats.get_active_id().unwrap_or_default());
+            // The entries are read and written in the background, as a large journal takes a while to export.
+            let operation = busy.begin();
+            let (query, notifier, background_path) = (query.clone(), notifier.clone(), path.clone());
+            worker::spawn(move || query.export(format, &background_path), move |result| {
+                operation.end();
+                match result {
+                    Ok(()) => notifier.success(format!("the journal has been exported to {}", path.display())),
+                    Err(why) => notifier.failure(format!("unable to export the journal to {}", path.display()),
+                        why.to_string()),
+                }
+            });
+        }
+    }
+
+    dialog.destroy();
+}
@@ -0,0 +1,92 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use gtk::*;
+use crate::systemd::machined::{self, Machine};
+use crate::systemd::Error;
+use super::notify::Notifier;
+use super::worker;
+
+/// The list of containers and virtual machines in the Machines `gtk::Stack`, along with the machines that it displays.
+#[derive(Clone)]
+pub struct Machines {
+    tree: TreeView,
+    machines: Rc<RefCell<Vec<Machine>>>,
+}
+
+impl Machines {
+    /// Creates the columns of the Machines `gtk::Stack`.
+    pub fn setup(builder: &Builder) -> Machines {
+        let machines_tree: TreeView = builder.get_object("machines_tree").unwrap();
+
+        // The `ListStore` determines what types can be stored for the `TreeView`.
+        let machines_store = ListStore::new(&[Type::String, Type::String, Type::String, Type::String]);
+
+        // A simple macro for adding a column to the machines tree.
+        macro_rules! add_column {
+            ($title:expr, $id:expr) => {{
+                let column   = TreeViewColumn::new();
+                let renderer = CellRendererText::new();
+                column.set_title($title);
+                column.set_resizable(true);
+                column.pack_start(&renderer, true);
+                column.add_attribute(&renderer, "text", $id);
+                machines_tree.append_column(&column);
+            }}
+        }
+
+        add_column!("Name", 0);
+        add_column!("Class", 1);
+        add_column!("State", 2);
+        add_column!("Leader", 3);
+        machines_tree.set_model(Some(&machines_store));
+
+        Machines { tree: machines_tree, machines: Rc::new(RefCell::new(Vec::new())) }
+    }
+
+    /// Lists the machines of `systemd-machined` in the background, replacing the machines that are displayed.
+    pub fn refresh(&self, notifier: &Notifier) {
+        let (machines, notifier) = (self.clone(), notifier.clone());
+        worker::spawn(machined::list_machines, move |listed| {
+            let listed = listed.unwrap_or_else(|why| {
+                notifier.failure(String::from("unable to list the machines"), why.to_string());
+                Vec::new()
+            });
+
+            if let Some(store) = machines.tree.get_model().and_then(|model| model.downcast::<ListStore>().ok()) {
+                store.clear();
+                for machine in &listed {
+                    let leader = machine.leader.map(|leader| leader.to_string()).unwrap_or_default();
+                    store.insert_with_values(None, &[0, 1, 2, 3],
+                        &[&machine.name, &machine.class, &machine.state, &leader]);
+                }
+            }
+            *machines.machines.borrow_mut() = listed;
+        });
+    }
+
+    /// Obtains the machine that is selected in the list.
+    pub fn selected(&self) -> Option<Machine> {
+        let (paths, _) = self.tree.get_selection().get_selected_rows();
+        let index = paths.first().and_then(|path| path.get_indices().first().cloned())?;
+        self.machines.borrow().get(index as usize).cloned()
+    }
+
+    /// Performs an action upon the selected machine in the background, reporting the outcome through the notifier
+    /// and refreshing the list once the action has completed.
+    pub fn control(&self, action: fn(&str) -> Result<(), Error>, past_tense: &'static str, notifier: &Notifier) {
+        let machine = match self.selected() {
+            Some(machine) => machine,
+            None => return,
+        };
+
+        let (machines, notifier) = (self.clone(), notifier.clone());
+        let name = machine.name.clone();
+        worker::spawn(move || action(&name), move |result| {
+            match result {
+                Ok(()) => notifier.success(format!("{} has been {}", machine.name, past_tense)),
+                Err(why) => notifier.failure(format!("{} could not be {}", machine.name, past_tense), why.to_string()),
+            }
+            machines.refresh(&notifier);
+        });
+    }
+}
@@ -1,42 +1,254 @@
-use std::fs;
-use std::io::Write;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
 
+use crate::systemd::backend::{CombinedBackend, LocalBackend, SystemdBackend};
 use crate::systemd::dbus::dbus::BusType;
-use crate::systemd::dbus::{self, Dbus};
-use crate::systemd::systemctl::Systemctl;
-use crate::systemd::{self, SystemdUnit};
+use crate::systemd::diagnostics::Diagnostics;
+use crate::systemd::machined::{self, MachineBackend};
+use crate::systemd::resources::UnitResources;
+use crate::systemd::{self, SystemdUnit, UnitState};
 
 mod analyze;
+mod batch;
 mod button_layout;
+mod connect;
+mod context_menu;
+mod diagnostics;
+mod journal;
+mod machines;
+mod notify;
+mod store;
 mod units;
+mod worker;
+use self::batch::BatchAction;
 use self::button_layout::ButtonLayout;
+use self::context_menu::MenuAction;
+use self::machines::Machines;
+use self::notify::Notifier;
+use self::store::UnitStore;
+use self::worker::Busy;
 
+use gdk;
 use gdk::enums::key;
 use gtk::prelude::*;
 use gtk::Builder;
-use gtk::{self, Image};
-
-/// Updates the status icon for the selected unit
-fn update_icon(icon: &Image, state: bool) {
-    if state {
-        icon.set_from_stock("gtk-yes", 4);
-    } else {
-        icon.set_from_stock("gtk-no", 4);
+use gtk;
+
+/// Updates the associated journal `TextView` with the contents of the unit's journal log, which is read from the
+/// backend in the background.
+fn update_journal(journal: &gtk::TextView, unit: &SystemdUnit, backend: Arc<dyn SystemdBackend>) {
+    let journal = journal.clone();
+    let unit = unit.clone();
+    worker::spawn(move || backend.journal(&unit.journal_query()), move |entries| {
+        let entries = entries.unwrap_or_else(|why| format!("Unable to read the journal: {}", why));
+        journal.get_buffer().map(|buffer| buffer.set_text(entries.as_str()));
+    });
+}
+
+/// The widgets in the right pane and header bar that display the details of the selected unit.
+#[derive(Clone)]
+struct Details {
+    header: gtk::Label,
+    unit_info: gtk::TextView,
+    dependencies: gtk::TextView,
+    unit_journal: gtk::TextView,
+    ablement_switch: gtk::Switch,
+    start_button: gtk::Button,
+    stop_button: gtk::Button,
+    save_button: gtk::Button,
+    selected: Rc<RefCell<String>>,
+    store: UnitStore,
+}
+
+impl Details {
+    fn new(builder: &Builder, store: &UnitStore) -> Details {
+        Details {
+            header: builder.get_object("header_service_label").unwrap(),
+            unit_info: builder.get_object("unit_info").unwrap(),
+            dependencies: builder.get_object("dependencies_view").unwrap(),
+            unit_journal: builder.get_object("unit_journal_view").unwrap(),
+            ablement_switch: builder.get_object("ablement_switch").unwrap(),
+            start_button: builder.get_object("start_button").unwrap(),
+            stop_button: builder.get_object("stop_button").unwrap(),
+            save_button: builder.get_object("save_button").unwrap(),
+            selected: Rc::new(RefCell::new(String::new())),
+            store: store.clone(),
+        }
+    }
+
+    /// Collects the details of the unit in the background and displays them once they have been collected,
+    /// unless another unit has been selected in the meantime.
+    fn load(&self, unit: &SystemdUnit, busy: &Busy) {
+        *self.selected.borrow_mut() = unit.key();
+        self.header.set_label(&unit.name);
+        // The file of the previously selected unit may not be saved over that of this unit while it is loading.
+        self.save_button.set_sensitive(false);
+        let operation = busy.begin();
+
+        let details = self.clone();
+        let unit = unit.clone();
+        let (backend, generation) = (self.store.backend(), self.store.generation());
+        worker::spawn(move || units::UnitDetails::collect(&*backend, &unit), move |collected| {
+            operation.end();
+            // The details of a unit of the previous manager are discarded if the manager has been switched.
+            if generation != details.store.generation() {
+                return
+            }
+            // The state of the unit was reloaded along with its details, so the store is brought up to date.
+            details.store.update(collected.unit.clone());
+            if *details.selected.borrow() == collected.unit.key() {
+                details.show(&collected);
+            }
+        });
+    }
+
+    /// Displays the collected details of a unit.
+    fn show(&self, unit: &units::UnitDetails) {
+        // Set the header label as the description if available, or the unit name if not.
+        self.header.set_label(unit.description.as_ref().unwrap_or(&unit.unit.name));
+        // Write the collected information to the unit file's textivew buffer.
+        self.unit_info.get_buffer().map(|buffer| buffer.set_text(unit.info.as_str()));
+        // The unit file may only be edited and saved if the backend is able to provide it.
+        self.unit_info.set_editable(unit.editable);
+        self.save_button.set_sensitive(unit.editable);
+        // Update the dependency list with the list of dependencies for that unit.
+        self.dependencies.get_buffer().map(|buffer| buffer.set_text(unit.dependencies.as_str()));
+        // Update the unit's journal view
+        self.unit_journal.get_buffer().map(|buffer| buffer.set_text(unit.journal.as_str()));
+        // If the unit is enabled, set the state and active status as true.
+        self.ablement_switch.set_active(unit.enabled());
+        self.ablement_switch.set_state(unit.enabled());
+        // Use the unit active status to determine which button should be currently visible.
+        self.start_button.set_visible(!unit.active());
+        self.stop_button.set_visible(unit.active());
     }
 }
 
-/// Updates the associated journal `TextView` with the contents of the unit's journal log.
-fn update_journal(journal: &gtk::TextView, unit: &SystemdUnit) {
-    journal
-        .get_buffer()
-        .map(|buffer| buffer.set_text(unit.get_journal().as_str()));
+/// Obtains the lists of services, sockets, and timers from the builder.
+fn get_unit_lists(builder: &Builder) -> units::UnitLists {
+    units::UnitLists {
+        stack: builder.get_object("unit_stack").unwrap(),
+        services: builder.get_object("services_tree").unwrap(),
+        sockets: builder.get_object("sockets_tree").unwrap(),
+        timers: builder.get_object("timers_tree").unwrap(),
+    }
 }
 
-pub fn refresh_all_units(bustype: BusType, builder: &Builder) {
-    let unit_stack: gtk::Stack = builder.get_object("unit_stack").unwrap();
-    let services_list: gtk::ListBox = builder.get_object("services_list").unwrap();
-    let sockets_list: gtk::ListBox = builder.get_object("sockets_list").unwrap();
-    let timers_list: gtk::ListBox = builder.get_object("timers_list").unwrap();
+/// Empties the unit lists and collects the units of the store's backend in the background, replacing the contents
+/// of the store and populating the lists once the units have been collected.
+fn refresh_all_units(builder: &Builder, store: &UnitStore, details: &Details, notifier: &Notifier, busy: &Busy) {
+    let lists = get_unit_lists(builder);
+    for list in lists.all().iter() {
+        units::UnitLists::model(list).map(|model| model.clear());
+    }
+
+    // The bus of each unit is displayed when the units of both buses are listed together.
+    let combined_button: gtk::ToggleButton = builder.get_object("combined_button").unwrap();
+    lists.show_bus(combined_button.get_active());
+
+    let operation = busy.begin();
+    let (backend, generation) = (store.backend(), store.generation());
+    let builder = builder.clone();
+    let store = store.clone();
+    let details = details.clone();
+    let notifier = notifier.clone();
+    let busy = busy.clone();
+    worker::spawn(
+        move || {
+            // Initialize all of the services, sockets, and timers.
+            backend.list_available_unit_files().map(|(unit_files, unavailable)| (
+                systemd::collect_togglable_services(&unit_files),
+                systemd::collect_togglable_sockets(&unit_files),
+                systemd::collect_togglable_timers(&unit_files),
+                unit_files,
+                unavailable,
+            ))
+        },
+        move |result| {
+            operation.end();
+            // The units of the previous manager are discarded, as the units of the current manager are being listed.
+            if generation != store.generation() {
+                return
+            }
+            let (services, sockets, timers, unit_files, unavailable) = match result {
+                Ok(units) => units,
+                Err(why) => {
+                    // The lists remain empty, and the user may try again once the bus is available.
+                    store.replace(&[]);
+                    let retry_notifier = notifier.clone();
+                    notifier.failure_with_retry(format!("unable to list the units of {}", store.backend().name()),
+                        why.to_string(), move || {
+                            refresh_all_units(&builder, &store, &details, &retry_notifier, &busy)
+                        });
+                    return
+                }
+            };
+            store.replace(&unit_files);
+
+            // The units that could be listed are shown, while the user is told of the bus that could not be reached.
+            if let Some(why) = unavailable {
+                notifier.failure(format!("only some of the units of {} could be listed", store.backend().name()),
+                    why.to_string());
+            }
+
+            // Initializes the rows for a given unit list.
+            macro_rules! initialize_units {
+                ($units:ident, $list:expr) => {{
+                    if let Some(model) = units::UnitLists::model(&$list) {
+                        for unit in &$units {
+                            units::insert_row(&model, unit, &store);
+                        }
+                    }
+                }};
+            }
+
+            initialize_units!(services, lists.services);
+            initialize_units!(sockets, lists.sockets);
+            initialize_units!(timers, lists.timers);
+
+            // Select the first unit of the visible list, which loads its details.
+            lists.visible().map(units::UnitLists::select_first);
+
+            // The resources of the listed units are collected afterwards, as they take a request for each unit.
+            let listed: Vec<SystemdUnit> = services.into_iter().chain(sockets).chain(timers).collect();
+            let backend = store.backend();
+            worker::spawn(
+                move || {
+                    listed.iter()
+                        .map(|unit| (unit.key(), UnitResources::collect(&*backend, unit)))
+                        .collect::<Vec<_>>()
+                },
+                move |collected| {
+                    if generation != store.generation() {
+                        return
+                    }
+                    for (key, resources) in &collected {
+                        store.update_resources(key, resources);
+                    }
+                },
+            );
+        },
+    );
+}
+
+/// Switches every view to the manager of the given backend, naming the manager in the header bar unless it is the
+/// manager of the local machine.
+fn switch_manager(backend: Arc<dyn SystemdBackend>, remote: bool, builder: &Builder, store: &UnitStore,
+        details: &Details, notifier: &Notifier, busy: &Busy) {
+    let left_bar: gtk::HeaderBar = builder.get_object("left_bar").unwrap();
+    let name = backend.name();
+    left_bar.set_subtitle(if remote { Some(name.as_str()) } else { None });
+    store.set_backend(backend);
+    refresh_all_units(builder, store, details, notifier, busy);
+    store.watch(notifier);
+    analyze::refresh(builder, store);
+}
+
+/// Programs the actions that are performed on the units. The signals are connected once, and every action
+/// obtains the selected unit from the store, so the lists may be refreshed any number of times.
+fn setup_units(builder: &Builder, store: &UnitStore, details: &Details, notifier: &Notifier, busy: &Busy) {
+    let lists = get_unit_lists(builder);
     let unit_info: gtk::TextView = builder.get_object("unit_info").unwrap();
     let ablement_switch: gtk::Switch = builder.get_object("ablement_switch").unwrap();
     let start_button: gtk::Button = builder.get_object("start_button").unwrap();
@@ -47,20 +259,27 @@ pub fn refresh_all_units(bustype: BusType, builder: &Builder) {
     let services_button: gtk::Button = builder.get_object("services_button").unwrap();
     let sockets_button: gtk::Button = builder.get_object("sockets_button").unwrap();
     let timers_button: gtk::Button = builder.get_object("timers_button").unwrap();
+    let unit_search: gtk::SearchEntry = builder.get_object("unit_search").unwrap();
     let unit_journal: gtk::TextView = builder.get_object("unit_journal_view").unwrap();
-    let header_service_label: gtk::Label = builder.get_object("header_service_label").unwrap();
     let systemd_menu_label: gtk::Label = builder.get_object("systemd_menu_label").unwrap();
     let systemd_units_button: gtk::MenuButton = builder.get_object("systemd_units_button").unwrap();
     let main_window_stack: gtk::Stack = builder.get_object("main_window_stack").unwrap();
     let systemd_units: gtk::Button = builder.get_object("systemd_units").unwrap();
     let systemd_analyze: gtk::Button = builder.get_object("systemd_analyze").unwrap();
+    let systemd_journal: gtk::Button = builder.get_object("systemd_journal").unwrap();
+    let systemd_machines: gtk::Button = builder.get_object("systemd_machines").unwrap();
     let systemd_menu_popover: gtk::PopoverMenu =
         builder.get_object("systemd_menu_popover").unwrap();
-    let dependencies_view: gtk::TextView = builder.get_object("dependencies_view").unwrap();
     let journal_refresh: gtk::Button = builder.get_object("refresh_log").unwrap();
+    let journal_export: gtk::Button = builder.get_object("export_log").unwrap();
+    let main_window: gtk::Window = builder.get_object("main_window").unwrap();
     let left_bar: gtk::HeaderBar = builder.get_object("left_bar").unwrap();
     let analyze_header: gtk::HeaderBar = builder.get_object("analyze_bar").unwrap();
     let units_header: gtk::HeaderBar = builder.get_object("right_bar").unwrap();
+    let journal_header: gtk::HeaderBar = builder.get_object("journal_bar").unwrap();
+    let machines_header: gtk::HeaderBar = builder.get_object("machines_bar").unwrap();
+    let machines_refresh: gtk::Button = builder.get_object("machines_refresh").unwrap();
+    let system_journal_refresh: gtk::Button = builder.get_object("system_journal_refresh").unwrap();
 
     {
         // Set the window controls to the left if the button layout is `Left`, else set it to the right.
@@ -68,176 +287,118 @@ pub fn refresh_all_units(bustype: BusType, builder: &Builder) {
         left_bar.set_show_close_button(!layout_boolean);
         units_header.set_show_close_button(layout_boolean);
         analyze_header.set_show_close_button(layout_boolean);
+        journal_header.set_show_close_button(layout_boolean);
+        machines_header.set_show_close_button(layout_boolean);
     }
 
-    macro_rules! empty_lists {
-        ($list:expr) => {{
-            let children = $list.get_children();
-            for i in &children {
-                $list.remove(i);
-            }
-        }};
-    }
-
-    empty_lists!(services_list);
-    empty_lists!(sockets_list);
-    empty_lists!(timers_list);
-
     macro_rules! units_menu_clicked {
-        ($units_button:ident, $units:ident, $list:ident, $unit_type:expr) => {{
+        ($units_button:ident, $list:expr, $unit_type:expr) => {{
             let label           = unit_menu_label.clone();
-            let stack           = unit_stack.clone();
+            let stack           = lists.stack.clone();
             let popover         = unit_popover.clone();
-            let $units          = $units.clone();
-            let $list           = $list.clone();
-            let unit_info       = unit_info.clone();
-            let ablement_switch = ablement_switch.clone();
-            let header          = header_service_label.clone();
-            let start_button    = start_button.clone();
-            let stop_button     = stop_button.clone();
-            let dependencies    = dependencies_view.clone();
-            let unit_journal    = unit_journal.clone();
+            let list            = $list.clone();
+            let store           = store.clone();
+            let details         = details.clone();
+            let busy            = busy.clone();
             $units_button.connect_clicked(move |_| {
                 stack.set_visible_child_name($unit_type);
                 label.set_text($unit_type);
                 popover.set_visible(false);
-                if let Some(row) = $list.get_row_at_index(0) {
-                    $list.select_row(Some(&row));
-                    let unit = &$units[row.get_index() as usize];
-                    // Obtain information from the unit's file.
-                    let info = unit.get_info();
-                    // Set the header label as the description if available, or the unit name if not.
-                    header.set_label(systemd::get_unit_description(&info).map_or(&unit.name, |desc| desc));
-                    // Write the collected information to the unit file's textivew buffer.
-                    unit_info.get_buffer().map(|buffer| buffer.set_text(info.as_str()));
-                    // Update the dependency list with the list of dependencies for that unit.
-                    dependencies.get_buffer().map(|buffer| buffer.set_text(unit.list_dependencies().as_str()));
-                    // Update the unit's journal view
-                    update_journal(&unit_journal, &unit);
-                    // If the unit is enabled, set the state and active status as true.
-                    let unit_enabled = unit.is_enabled();
-                    ablement_switch.set_active(unit_enabled);
-                    ablement_switch.set_state(unit_enabled);
-                    // Use the unit active status to determine which button should be currently visible.
-                    let status = unit.is_active();
-                    start_button.set_visible(!status);
-                    stop_button.set_visible(status);
+                match units::UnitLists::selected_in(&list, &store) {
+                    Some(unit) => details.load(&unit, &busy),
+                    None => units::UnitLists::select_first(&list),
                 }
             });
         }}
     }
 
-    // Initializes the units for a given unit list
-    macro_rules! initialize_units {
-        ($units:ident, $list:ident, $active_icons:ident, $enable_icons:ident) => {{
-            for unit in $units.clone() {
-                let mut unit_row = gtk::ListBoxRow::new();
-                units::create_row(&mut unit_row, &unit, &mut $active_icons, &mut $enable_icons);
-                $list.insert(&unit_row, -1);
-            }
-            $list.show_all();
-        }};
-    }
-
-    // Programs the row_selected signal for a given unit list.
+    // Programs the changed signal of a given unit list's selection.
     macro_rules! signal_row_selected {
-        ($units:ident, $list:ident) => {{
-            let $units          = $units.clone();
-            let $list           = $list.clone();
-            let unit_info       = unit_info.clone();
-            let ablement_switch = ablement_switch.clone();
-            let header          = header_service_label.clone();
-            let stop_button     = stop_button.clone();
-            let start_button    = start_button.clone();
-            let dependencies    = dependencies_view.clone();
-            let unit_journal    = unit_journal.clone();
-            $list.connect_row_selected(move |_, row| {
-                if let Some(row) = row.clone() {
-                    let unit = &$units[row.get_index() as usize];
-                    // Obtain information from the unit's file.
-                    let info = unit.get_info();
-                    // Set the header label as the description if available, or the unit name if not.
-                    header.set_label(systemd::get_unit_description(&info).map_or(&unit.name, |desc| desc));
-                    // Write the collected information to the unit file's textivew buffer.
-                    unit_info.get_buffer().map(|buffer| buffer.set_text(info.as_str()));
-                    // Update the dependency list with the list of dependencies for that unit.
-                    dependencies.get_buffer().map(|buffer| buffer.set_text(unit.list_dependencies().as_str()));
-                    // Update the unit's journal view
-                    update_journal(&unit_journal, &unit);
-                    // If the unit is enabled, set the state and active status as true.
-                    let unit_enabled = unit.is_enabled();
-                    ablement_switch.set_active(unit_enabled);
-                    ablement_switch.set_state(unit_enabled);
-                    // Use the unit active status to determine which button should be currently visible.
-                    let status = unit.is_active();
-                    start_button.set_visible(!status);
-                    stop_button.set_visible(status);
+        ($list:expr) => {{
+            let list            = $list.clone();
+            let store           = store.clone();
+            let details         = details.clone();
+            let busy            = busy.clone();
+            $list.get_selection().connect_changed(move |_| {
+                if let Some(unit) = units::UnitLists::selected_in(&list, &store) {
+                    details.load(&unit, &busy);
                 }
             });
         }}
     }
 
-    // Initialize all of the services, sockets, and timers.
-    let unit_files = dbus::list_unit_files(bustype);
-    let services = systemd::collect_togglable_services(&unit_files);
-    let sockets = systemd::collect_togglable_sockets(&unit_files);
-    let timers = systemd::collect_togglable_timers(&unit_files);
-
-    // Create vectors to contain status icons that can later be manipulated.
-    let mut services_icons_active = Vec::new();
-    let mut services_icons_enabled = Vec::new();
-    let mut sockets_icons_active = Vec::new();
-    let mut sockets_icons_enabled = Vec::new();
-    let mut timers_icons_active = Vec::new();
-    let mut timers_icons_enabled = Vec::new();
-    // Initialize the rows in each of the ListBoxes.
-    initialize_units!(
-        services,
-        services_list,
-        services_icons_active,
-        services_icons_enabled
-    );
-    initialize_units!(
-        sockets,
-        sockets_list,
-        sockets_icons_active,
-        sockets_icons_enabled
-    );
-    initialize_units!(
-        timers,
-        timers_list,
-        timers_icons_active,
-        timers_icons_enabled
-    );
-    // Program what happens when a row is selected for each of the ListBoxes.
-    signal_row_selected!(timers, timers_list);
-    signal_row_selected!(services, services_list);
-    signal_row_selected!(sockets, sockets_list);
+    // Create the columns of each unit list, whose rows are displayed if they match the search text and the filters.
+    let filter = Rc::new(RefCell::new(units::UnitFilter::default()));
+    for list in lists.all().iter() {
+        units::setup_list(list, store, &filter);
+    }
+
+    // Program what happens when a row is selected for each of the lists.
+    signal_row_selected!(lists.timers);
+    signal_row_selected!(lists.services);
+    signal_row_selected!(lists.sockets);
     // Program what happens when a menu button is clicked.
-    units_menu_clicked!(services_button, services, services_list, "Services");
-    units_menu_clicked!(sockets_button, sockets, sockets_list, "Sockets");
-    units_menu_clicked!(timers_button, timers, timers_list, "Timers");
+    units_menu_clicked!(services_button, lists.services, "Services");
+    units_menu_clicked!(sockets_button, lists.sockets, "Sockets");
+    units_menu_clicked!(timers_button, lists.timers, "Timers");
+
+    {
+        // NOTE: Only display the units that match the search text and the state filters
+        {
+            let (lists, filter) = (lists.clone(), filter.clone());
+            unit_search.connect_search_changed(move |search| {
+                filter.borrow_mut().text = search.get_text().unwrap_or_default();
+                lists.refilter();
+            });
+        }
+
+        // Programs a state filter's check button to display or hide the units of that state.
+        macro_rules! filter_toggled {
+            ($id:expr, $field:ident) => {{
+                let check: gtk::CheckButton = builder.get_object($id).unwrap();
+                let (lists, filter) = (lists.clone(), filter.clone());
+                check.connect_toggled(move |check| {
+                    filter.borrow_mut().$field = check.get_active();
+                    lists.refilter();
+                });
+            }}
+        }
+
+        filter_toggled!("filter_active", active);
+        filter_toggled!("filter_inactive", inactive);
+        filter_toggled!("filter_failed", failed);
+        filter_toggled!("filter_enabled", enabled);
+        filter_toggled!("filter_disabled", disabled);
+        filter_toggled!("filter_masked", masked);
+    }
 
     {
         // NOTE: Refresh the journal when the refresh button is clicked
-        let services = services.clone();
-        let services_list = services_list.clone();
-        let sockets = sockets.clone();
-        let sockets_list = sockets_list.clone();
-        let timers = timers.clone();
-        let timers_list = timers_list.clone();
-        let unit_stack = unit_stack.clone();
+        let lists = lists.clone();
+        let store = store.clone();
         let unit_journal = unit_journal.clone();
-
         journal_refresh.connect_clicked(move |_| {
-            if let Some(child) = unit_stack.get_visible_child_name() {
-                let unit = match child.as_str() {
-                    "Services" => units::get_current_unit(&services, &services_list),
-                    "Sockets" => units::get_current_unit(&sockets, &sockets_list),
-                    "Timers" => units::get_current_unit(&timers, &timers_list),
-                    _ => unreachable!(),
-                };
-                update_journal(&unit_journal, unit);
+            if let Some(unit) = lists.selected_unit(&store) {
+                update_journal(&unit_journal, &unit, store.backend());
+            }
+        });
+    }
+
+    {
+        // NOTE: Export the journal when the export button is clicked
+        let lists = lists.clone();
+        let store = store.clone();
+        let main_window = main_window.clone();
+        let notifier = notifier.clone();
+        let busy = busy.clone();
+        journal_export.connect_clicked(move |_| {
+            if let Some(unit) = lists.selected_unit(&store) {
+                match store.backend().journal_query(&unit) {
+                    Ok(query) => journal::export(&main_window, &query, &unit.name, &notifier, &busy),
+                    Err(why) => {
+                        notifier.failure(format!("unable to export the journal of {}", unit.name), why.to_string())
+                    }
+                }
             }
         });
     }
@@ -249,6 +410,8 @@ pub fn refresh_all_units(bustype: BusType, builder: &Builder) {
         let systemd_menu_label = systemd_menu_label.clone();
         let units_header = units_header.clone();
         let analyze_header = analyze_header.clone();
+        let journal_header = journal_header.clone();
+        let machines_header = machines_header.clone();
         let systemd_units_button = systemd_units_button.clone();
         let popover = systemd_menu_popover.clone();
         systemd_analyze.connect_clicked(move |_| {
@@ -257,16 +420,63 @@ pub fn refresh_all_units(bustype: BusType, builder: &Builder) {
             systemd_units_button.set_visible(false);
             units_header.set_visible(false);
             analyze_header.set_visible(true);
+            journal_header.set_visible(false);
+            machines_header.set_visible(false);
             popover.set_visible(false);
         });
     }
 
     {
-        // NOTE: Program the Systemd Unit Button
+        // NOTE: Program the Systemd Journal Button
+        let main_window_stack = main_window_stack.clone();
+        let systemd_menu_label = systemd_menu_label.clone();
+        let units_header = units_header.clone();
+        let analyze_header = analyze_header.clone();
+        let journal_header = journal_header.clone();
+        let machines_header = machines_header.clone();
         let systemd_units_button = systemd_units_button.clone();
+        let popover = systemd_menu_popover.clone();
+        systemd_journal.connect_clicked(move |_| {
+            main_window_stack.set_visible_child_name("Systemd Journal");
+            systemd_menu_label.set_label("Systemd Journal");
+            systemd_units_button.set_visible(false);
+            units_header.set_visible(false);
+            analyze_header.set_visible(false);
+            journal_header.set_visible(true);
+            machines_header.set_visible(false);
+            popover.set_visible(false);
+            system_journal_refresh.clicked();
+        });
+    }
+
+    {
+        // NOTE: Program the Systemd Machines Button
         let main_window_stack = main_window_stack.clone();
         let systemd_menu_label = systemd_menu_label.clone();
+        let units_header = units_header.clone();
+        let analyze_header = analyze_header.clone();
+        let journal_header = journal_header.clone();
+        let machines_header = machines_header.clone();
+        let systemd_units_button = systemd_units_button.clone();
+        let popover = systemd_menu_popover.clone();
+        systemd_machines.connect_clicked(move |_| {
+            main_window_stack.set_visible_child_name("Systemd Machines");
+            systemd_menu_label.set_label("Systemd Machines");
+            systemd_units_button.set_visible(false);
+            units_header.set_visible(false);
+            analyze_header.set_visible(false);
+            journal_header.set_visible(false);
+            machines_header.set_visible(true);
+            popover.set_visible(false);
+            machines_refresh.clicked();
+        });
+    }
+
+    {
+        // NOTE: Program the Systemd Unit Button
         let systemd_units_button = systemd_units_button.clone();
+        let main_window_stack = main_window_stack.clone();
+        let systemd_menu_label = systemd_menu_label.clone();
         let popover = systemd_menu_popover.clone();
         systemd_units.connect_clicked(move |_| {
             main_window_stack.set_visible_child_name("Systemd Units");
@@ -274,73 +484,21 @@ pub fn refresh_all_units(bustype: BusType, builder: &Builder) {
             systemd_units_button.set_visible(true);
             units_header.set_visible(true);
             analyze_header.set_visible(false);
+            journal_header.set_visible(false);
+            machines_header.set_visible(false);
             popover.set_visible(false);
         });
     }
 
     {
         // NOTE: Implement the {dis, en}able button
-        let services = services.clone();
-        let services_list = services_list.clone();
-        let services_icons_enabled = services_icons_enabled.clone();
-        let sockets = sockets.clone();
-        let sockets_list = sockets_list.clone();
-        let sockets_icons_enabled = sockets_icons_enabled.clone();
-        let timers = timers.clone();
-        let timers_list = timers_list.clone();
-        let timers_icons_enabled = timers_icons_enabled.clone();
-        let unit_stack = unit_stack.clone();
+        let lists = lists.clone();
+        let store = store.clone();
+        let notifier = notifier.clone();
+        let busy = busy.clone();
         ablement_switch.connect_state_set(move |switch, enabled| {
-            if let Some(child) = unit_stack.get_visible_child_name() {
-                let (unit, icon) = match child.as_str() {
-                    "Services" => units::get_current_unit_icons(
-                        &services,
-                        &services_list,
-                        &services_icons_enabled,
-                    ),
-                    "Sockets" => units::get_current_unit_icons(
-                        &sockets,
-                        &sockets_list,
-                        &sockets_icons_enabled,
-                    ),
-                    "Timers" => {
-                        units::get_current_unit_icons(&timers, &timers_list, &timers_icons_enabled)
-                    }
-                    _ => unreachable!(),
-                };
-                if enabled && !unit.is_enabled() {
-                    match unit.enable() {
-                        Ok(unit_was_enabled) => {
-                            if unit_was_enabled {
-                                println!("systemd-manager: {} was already enabled", unit.name);
-                            } else {
-                                println!("systemd-manager: {} has been enabled", unit.name);
-                            }
-                            update_icon(icon, true);
-                        }
-                        Err(message) => println!(
-                            "systemd-manager: {} could not be enabled: {}",
-                            unit.name, message
-                        ),
-                    }
-                    switch.set_state(true);
-                } else if !enabled && unit.is_enabled() {
-                    match unit.disable() {
-                        Ok(unit_was_disabled) => {
-                            if unit_was_disabled {
-                                println!("systemd-manager: {} was already disabled", unit.name);
-                            } else {
-                                println!("systemd-manager: {} has been disabled", unit.name);
-                            }
-                            update_icon(icon, false);
-                        }
-                        Err(message) => println!(
-                            "systemd-manager: {} could not be disabled: {}",
-                            unit.name, message
-                        ),
-                    }
-                    switch.set_state(false);
-                }
+            if let Some(unit) = lists.selected_unit(&store) {
+                set_enablement(&unit, switch, enabled, &store, &notifier, &busy);
             }
             gtk::Inhibit(true)
         });
@@ -348,94 +506,29 @@ pub fn refresh_all_units(bustype: BusType, builder: &Builder) {
 
     {
         // NOTE: Implement the start button
-        let services = services.clone();
-        let services_list = services_list.clone();
-        let sockets = sockets.clone();
-        let sockets_list = sockets_list.clone();
-        let timers = timers.clone();
-        let timers_list = timers_list.clone();
-        let services_icons_active = services_icons_active.clone();
-        let sockets_icons_active = sockets_icons_active.clone();
-        let timers_icons_active = timers_icons_active.clone();
-        let unit_stack = unit_stack.clone();
-        let start_button = start_button.clone();
+        let lists = lists.clone();
+        let store = store.clone();
         let stop_button = stop_button.clone();
+        let main_window = main_window.clone();
+        let notifier = notifier.clone();
+        let busy = busy.clone();
         start_button.connect_clicked(move |button| {
-            if let Some(child) = unit_stack.get_visible_child_name() {
-                let (unit, icon) = match child.as_str() {
-                    "Services" => units::get_current_unit_icons(
-                        &services,
-                        &services_list,
-                        &services_icons_active,
-                    ),
-                    "Sockets" => units::get_current_unit_icons(
-                        &sockets,
-                        &sockets_list,
-                        &sockets_icons_active,
-                    ),
-                    "Timers" => {
-                        units::get_current_unit_icons(&timers, &timers_list, &timers_icons_active)
-                    }
-                    _ => unreachable!(),
-                };
-                match unit.start() {
-                    None => {
-                        println!("systemd-manager: {} successfully started", unit.name);
-                        update_icon(icon, true);
-                        button.set_visible(false);
-                        stop_button.set_visible(true);
-                    }
-                    Some(error) => {
-                        println!("systemd-manager: {} failed to start: {}", unit.name, error)
-                    }
-                }
+            if let Some(unit) = lists.selected_unit(&store) {
+                start_unit(&unit, button, &stop_button, &main_window, &store, &notifier, &busy);
             }
         });
     }
 
     {
         // NOTE: Implement the stop button
-        let services = services.clone();
-        let services_list = services_list.clone();
-        let sockets = sockets.clone();
-        let sockets_list = sockets_list.clone();
-        let timers = timers.clone();
-        let timers_list = timers_list.clone();
-        let services_icons_active = services_icons_active.clone();
-        let sockets_icons_active = sockets_icons_active.clone();
-        let timers_icons_active = timers_icons_active.clone();
-        let unit_stack = unit_stack.clone();
+        let lists = lists.clone();
+        let store = store.clone();
         let start_button = start_button.clone();
-        let stop_button = stop_button.clone();
+        let notifier = notifier.clone();
+        let busy = busy.clone();
         stop_button.connect_clicked(move |button| {
-            if let Some(child) = unit_stack.get_visible_child_name() {
-                let (unit, icon) = match child.as_str() {
-                    "Services" => units::get_current_unit_icons(
-                        &services,
-                        &services_list,
-                        &services_icons_active,
-                    ),
-                    "Sockets" => units::get_current_unit_icons(
-                        &sockets,
-                        &sockets_list,
-                        &sockets_icons_active,
-                    ),
-                    "Timers" => {
-                        units::get_current_unit_icons(&timers, &timers_list, &timers_icons_active)
-                    }
-                    _ => unreachable!(),
-                };
-                match unit.stop() {
-                    None => {
-                        println!("systemd-manager: {} successfully stopped", unit.name);
-                        update_icon(icon, false);
-                        button.set_visible(false);
-                        start_button.set_visible(true);
-                    }
-                    Some(error) => {
-                        println!("systemd-manager: {} failed to stop: {}", unit.name, error)
-                    }
-                }
+            if let Some(unit) = lists.selected_unit(&store) {
+                stop_unit(&unit, &start_button, button, &store, &notifier, &busy);
             }
         });
     }
@@ -443,43 +536,236 @@ pub fn refresh_all_units(bustype: BusType, builder: &Builder) {
     {
         // NOTE: Save Button
         let unit_info = unit_info.clone();
-        let services = services.clone();
-        let services_list = services_list.clone();
-        let sockets = sockets.clone();
-        let sockets_list = sockets_list.clone();
-        let timers = timers.clone();
-        let timers_list = timers_list.clone();
-        let unit_stack = unit_stack.clone();
+        let lists = lists.clone();
+        let store = store.clone();
+        let notifier = notifier.clone();
+        let busy = busy.clone();
         save_unit_file.connect_clicked(move |_| {
             if let Some(buffer) = unit_info.get_buffer() {
                 let start = buffer.get_start_iter();
                 let end = buffer.get_end_iter();
                 if let Some(text) = buffer.get_text(&start, &end, true) {
-                    if let Some(child) = unit_stack.get_visible_child_name() {
-                        let unit = match child.as_str() {
-                            "Services" => units::get_current_unit(&services, &services_list),
-                            "Sockets" => units::get_current_unit(&sockets, &sockets_list),
-                            "Timers" => units::get_current_unit(&timers, &timers_list),
-                            _ => unreachable!(),
-                        };
-                        // Open the unit file with write access
-                        let status = fs::OpenOptions::new()
-                            .write(true)
-                            .open(&unit.path)
-                            // Attempt to write to the file, else return an error message.
-                            .map(|mut file| file.write(text.as_bytes()));
-
-                        if let Err(message) = status {
-                            println!(
-                                "systemd-manager: unable to save unit file: {}",
-                                message.to_string()
-                            );
-                        }
+                    if let Some(unit) = lists.selected_unit(&store) {
+                        save_unit(&unit, text, &store, &notifier, &busy);
                     }
                 }
             }
         });
     }
+
+    {
+        // NOTE: Perform the chosen action upon every unit that is selected in the visible list
+        let batch_popover: gtk::Popover = builder.get_object("unit_batch_popover").unwrap();
+
+        macro_rules! batch_clicked {
+            ($id:expr, $action:expr) => {{
+                let button: gtk::Button = builder.get_object($id).unwrap();
+                let (lists, store, main_window) = (lists.clone(), store.clone(), main_window.clone());
+                let (details, notifier, busy) = (details.clone(), notifier.clone(), busy.clone());
+                let popover = batch_popover.clone();
+                button.connect_clicked(move |_| {
+                    popover.set_visible(false);
+                    let selected = lists.selected_units(&store);
+                    let (lists, reload_store, details, reload_busy) = (lists.clone(), store.clone(),
+                        details.clone(), busy.clone());
+                    batch::run(&main_window, $action, selected, &store, &notifier, &busy, move || {
+                        // The details of the selected unit are collected again, as its state may have changed.
+                        if let Some(unit) = lists.selected_unit(&reload_store) {
+                            details.load(&unit, &reload_busy);
+                        }
+                    });
+                });
+            }}
+        }
+
+        batch_clicked!("batch_start", BatchAction::Start);
+        batch_clicked!("batch_stop", BatchAction::Stop);
+        batch_clicked!("batch_restart", BatchAction::Restart);
+        batch_clicked!("batch_enable", BatchAction::Enable);
+        batch_clicked!("batch_disable", BatchAction::Disable);
+        batch_clicked!("batch_mask", BatchAction::Mask);
+    }
+
+    {
+        // NOTE: Open the context menu of a unit's row, whose actions are performed upon that unit
+        let info_stack: gtk::Stack = builder.get_object("info_stack").unwrap();
+        let (lists_clone, store_clone) = (lists.clone(), store.clone());
+        let (details, notifier, busy) = (details.clone(), notifier.clone(), busy.clone());
+        let activate = move |action: MenuAction, unit: &SystemdUnit| match action {
+            MenuAction::Control(action) => {
+                let (lists, store) = (lists_clone.clone(), store_clone.clone());
+                let (details, reload_busy) = (details.clone(), busy.clone());
+                batch::perform(action, unit, &store_clone, &notifier, &busy, Rc::new(move || {
+                    // The details of the selected unit are collected again, as its state may have changed.
+                    if let Some(unit) = lists.selected_unit(&store) {
+                        details.load(&unit, &reload_busy);
+                    }
+                }));
+            }
+            MenuAction::Edit => {
+                info_stack.set_visible_child_name("File");
+                unit_info.grab_focus();
+            }
+            MenuAction::ViewJournal => info_stack.set_visible_child_name("Journal"),
+            MenuAction::CopyName => context_menu::copy(&unit.name),
+            MenuAction::CopyPath => context_menu::copy(&unit.path),
+        };
+        let activate: Rc<dyn Fn(MenuAction, &SystemdUnit)> = Rc::new(activate);
+
+        for list in lists.all().iter() {
+            context_menu::connect(list, store, activate.clone());
+        }
+    }
+}
+
+/// Enables or disables the unit in the background if its stored enablement differs from the requested state,
+/// reporting the outcome through the notifier.
+fn set_enablement(unit: &SystemdUnit, switch: &gtk::Switch, enable: bool, store: &UnitStore, notifier: &Notifier,
+        busy: &Busy) {
+    // The switch is also set when a unit is selected, which requires no action.
+    if enable == (unit.state == UnitState::Enabled) {
+        switch.set_state(enable);
+        return
+    }
+
+    let operation = busy.begin();
+    let action = if enable { "enabled" } else { "disabled" };
+    let (unit, switch, store, notifier, busy) = (unit.clone(), switch.clone(), store.clone(), notifier.clone(),
+        busy.clone());
+    let (backend, generation, background_unit) = (store.backend(), store.generation(), unit.clone());
+    worker::spawn(
+        move || {
+            let result = if enable { backend.enable(&background_unit) } else { backend.disable(&background_unit) };
+            (result, backend.reload_unit(&background_unit).unwrap_or(background_unit))
+        },
+        move |(result, reloaded)| {
+            operation.end();
+            store.update_current(generation, reloaded);
+            match result {
+                Ok(already) => {
+                    switch.set_state(enable);
+                    if already {
+                        notifier.success(format!("{} was already {}", unit.name, action));
+                    } else {
+                        notifier.success(format!("{} has been {}", unit.name, action));
+                    }
+                }
+                Err(why) => {
+                    switch.set_active(!enable);
+                    switch.set_state(!enable);
+                    let retry_notifier = notifier.clone();
+                    let message = format!("{} could not be {}", unit.name, action);
+                    notifier.failure_with_retry(message, why.to_string(), move || {
+                        set_enablement(&unit, &switch, enable, &store, &retry_notifier, &busy)
+                    });
+                }
+            }
+        },
+    );
+}
+
+/// Starts the unit in the background, reporting the outcome through the notifier and diagnosing the unit if it
+/// fails to start.
+fn start_unit(unit: &SystemdUnit, start_button: &gtk::Button, stop_button: &gtk::Button, window: &gtk::Window,
+        store: &UnitStore, notifier: &Notifier, busy: &Busy) {
+    let operation = busy.begin();
+    let (unit, start_button, stop_button) = (unit.clone(), start_button.clone(), stop_button.clone());
+    let (window, store, notifier, busy) = (window.clone(), store.clone(), notifier.clone(), busy.clone());
+    let (backend, generation, background_unit) = (store.backend(), store.generation(), unit.clone());
+    // The state of the unit is reloaded once the start job has run, so that the store reflects the outcome and a unit
+    // which failed to start is diagnosed like a start job that could not be queued.
+    let work = move || match backend.start(&background_unit) {
+        Ok(()) => {
+            let reloaded = backend.reload_settled(&background_unit).unwrap_or(background_unit);
+            (reloaded.check_started(), reloaded)
+        }
+        Err(why) => (Err(why), backend.reload_unit(&background_unit).unwrap_or(background_unit)),
+    };
+    worker::spawn(work, move |(result, reloaded)| {
+        operation.end();
+        store.update_current(generation, reloaded);
+        match result {
+            Ok(()) => {
+                notifier.success(format!("{} successfully started", unit.name));
+                start_button.set_visible(false);
+                stop_button.set_visible(true);
+            }
+            Err(why) => {
+                let error = why.to_string();
+                {
+                    let (unit, window, store, notifier) = (unit.clone(), window.clone(), store.clone(),
+                        notifier.clone());
+                    notifier.clone().failure_with_retry(format!("{} failed to start", unit.name), error.clone(),
+                        move || start_unit(&unit, &start_button, &stop_button, &window, &store, &notifier, &busy));
+                }
+                // The diagnostics are also collected in the background before they are displayed.
+                let (backend, background_unit) = (store.backend(), unit.clone());
+                worker::spawn(move || Diagnostics::collect(&*backend, &background_unit), move |collected| {
+                    diagnostics::show(&window, &unit.name, &error, &collected)
+                });
+            }
+        }
+    });
+}
+
+/// Stops the unit in the background, reporting the outcome through the notifier.
+fn stop_unit(unit: &SystemdUnit, start_button: &gtk::Button, stop_button: &gtk::Button, store: &UnitStore,
+        notifier: &Notifier, busy: &Busy) {
+    let operation = busy.begin();
+    let (unit, start_button, stop_button) = (unit.clone(), start_button.clone(), stop_button.clone());
+    let (store, notifier, busy) = (store.clone(), notifier.clone(), busy.clone());
+    let (backend, generation, background_unit) = (store.backend(), store.generation(), unit.clone());
+    // The state of the unit is reloaded once the stop job has run, so that the store does not keep a unit that is
+    // still deactivating, and a unit that is still running is reported as a failure.
+    let work = move || match backend.stop(&background_unit) {
+        Ok(()) => {
+            let reloaded = backend.reload_settled(&background_unit).unwrap_or(background_unit);
+            (reloaded.check_stopped(), reloaded)
+        }
+        Err(why) => (Err(why), backend.reload_unit(&background_unit).unwrap_or(background_unit)),
+    };
+    worker::spawn(work, move |(result, reloaded)| {
+        operation.end();
+        store.update_current(generation, reloaded);
+        match result {
+            Ok(()) => {
+                notifier.success(format!("{} successfully stopped", unit.name));
+                stop_button.set_visible(false);
+                start_button.set_visible(true);
+            }
+            Err(why) => {
+                let retry_notifier = notifier.clone();
+                notifier.failure_with_retry(format!("{} failed to stop", unit.name), why.to_string(), move || {
+                    stop_unit(&unit, &start_button, &stop_button, &store, &retry_notifier, &busy)
+                });
+            }
+        }
+    });
+}
+
+/// Writes the text to the unit's file through the store's backend in the background, reporting the outcome through
+/// the notifier.
+fn save_unit(unit: &SystemdUnit, text: String, store: &UnitStore, notifier: &Notifier, busy: &Busy) {
+    let operation = busy.begin();
+    let (unit, store, notifier, busy) = (unit.clone(), store.clone(), notifier.clone(), busy.clone());
+    let (backend, generation, background_unit, background_text) =
+        (store.backend(), store.generation(), unit.clone(), text.clone());
+    // The unit is reloaded afterwards, as systemd may describe the saved unit file differently.
+    let work = move || (backend.save_unit_file(&background_unit, &background_text),
+        backend.reload_unit(&background_unit).unwrap_or(background_unit));
+    worker::spawn(work, move |(result, reloaded)| {
+        operation.end();
+        store.update_current(generation, reloaded);
+        match result {
+            Ok(()) => notifier.success(format!("{} has been saved", unit.path)),
+            Err(message) => {
+                let retry_notifier = notifier.clone();
+                notifier.failure_with_retry(format!("unable to save {}", unit.path), message.to_string(), move || {
+                    save_unit(&unit, text.clone(), &store, &retry_notifier, &busy)
+                });
+            }
+        }
+    });
 }
 
 pub fn launch() {
@@ -491,22 +777,120 @@ pub fn launch() {
     let session_switch: gtk::Switch = builder.get_object("session_switch").unwrap();
     session_switch.set_active(true);
 
+    // Setup the notification bar, which reports the outcome of operations.
+    let notifier = Notifier::new(&builder);
+
+    // Setup the spinner, which is displayed while operations upon units are running in the background.
+    let busy = {
+        let spinner: gtk::Spinner = builder.get_object("operation_spinner").unwrap();
+        let ablement_switch: gtk::Switch = builder.get_object("ablement_switch").unwrap();
+        let start_button: gtk::Button = builder.get_object("start_button").unwrap();
+        let stop_button: gtk::Button = builder.get_object("stop_button").unwrap();
+        let batch_button: gtk::MenuButton = builder.get_object("unit_batch_button").unwrap();
+        // The save button is made sensitive by the details of the unit, as only some backends can save unit files.
+        Busy::new(spinner, vec![ablement_switch.upcast(), start_button.upcast(), stop_button.upcast(),
+            batch_button.upcast()])
+    };
+
+    // The backend of the current manager, and its units, are kept in a store that every view queries.
+    let store = UnitStore::new(Arc::new(LocalBackend::new(BusType::Session)));
     // Setup the Analyze stack
-    analyze::setup(&builder);
-    refresh_all_units(BusType::Session, &builder);
+    analyze::setup(&builder, &store);
+    // Setup the Journal stack
+    journal::setup(&builder, &notifier, &store, &busy);
+    // Setup the Units stack
+    let details = Details::new(&builder, &store);
+    setup_units(&builder, &store, &details, &notifier, &busy);
+    refresh_all_units(&builder, &store, &details, &notifier, &busy);
+    store.watch(&notifier);
+    store.follow_elapsed();
+
+    {
+        // NOTE: Connect to the manager that is chosen in the Connect To dialog
+        let connect_button: gtk::Button = builder.get_object("connect_button").unwrap();
+        let popover: gtk::PopoverMenu = builder.get_object("systemd_menu_popover").unwrap();
+        let (window, builder, store) = (window.clone(), builder.clone(), store.clone());
+        let (details, notifier, busy) = (details.clone(), notifier.clone(), busy.clone());
+        connect_button.connect_clicked(move |_| {
+            popover.set_visible(false);
+            if let Some(backend) = connect::run(&window) {
+                switch_manager(backend, true, &builder, &store, &details, &notifier, &busy);
+            }
+        });
+    }
+
+    {
+        // NOTE: Program the actions of the Machines stack
+        let machines = Machines::setup(&builder);
+        let refresh_button: gtk::Button = builder.get_object("machines_refresh").unwrap();
+        let units_button: gtk::Button = builder.get_object("machine_units_button").unwrap();
+        let start_button: gtk::Button = builder.get_object("machine_start_button").unwrap();
+        let poweroff_button: gtk::Button = builder.get_object("machine_poweroff_button").unwrap();
+        let terminate_button: gtk::Button = builder.get_object("machine_terminate_button").unwrap();
+
+        macro_rules! control_clicked {
+            ($button:ident, $action:expr, $past_tense:expr) => {{
+                let machines = machines.clone();
+                let notifier = notifier.clone();
+                $button.connect_clicked(move |_| machines.control($action, $past_tense, &notifier));
+            }}
+        }
+
+        control_clicked!(start_button, machined::start_machine, "started");
+        control_clicked!(poweroff_button, machined::poweroff_machine, "powered off");
+        control_clicked!(terminate_button, machined::terminate_machine, "terminated");
+
+        {
+            let machines = machines.clone();
+            let notifier = notifier.clone();
+            refresh_button.connect_clicked(move |_| machines.refresh(&notifier));
+        }
+
+        // The units of the selected machine are managed through the Units stack.
+        let systemd_units: gtk::Button = builder.get_object("systemd_units").unwrap();
+        let (builder, store) = (builder.clone(), store.clone());
+        let (details, notifier, busy) = (details.clone(), notifier.clone(), busy.clone());
+        units_button.connect_clicked(move |_| {
+            if let Some(machine) = machines.selected() {
+                match MachineBackend::new(&machine) {
+                    Ok(backend) => {
+                        switch_manager(Arc::new(backend), true, &builder, &store, &details, &notifier, &busy);
+                        systemd_units.clicked();
+                    }
+                    Err(why) => notifier.failure(format!("unable to manage the units of {}", machine.name),
+                        why.to_string()),
+                }
+            }
+        });
+    }
+
+    {
+        // NOTE: List the units of the system and session buses together while the combined button is active
+        let combined_button: gtk::ToggleButton = builder.get_object("combined_button").unwrap();
+        let session_switch = session_switch.clone();
+        let (builder, store) = (builder.clone(), store.clone());
+        let (details, notifier, busy) = (details.clone(), notifier.clone(), busy.clone());
+        combined_button.connect_toggled(move |button| {
+            let combined = button.get_active();
+            session_switch.set_sensitive(!combined);
+            let backend: Arc<dyn SystemdBackend> = if combined {
+                Arc::new(CombinedBackend::new())
+            } else {
+                let bustype = if session_switch.get_active() { BusType::Session } else { BusType::System };
+                Arc::new(LocalBackend::new(bustype))
+            };
+            switch_manager(backend, false, &builder, &store, &details, &notifier, &busy);
+        });
+    }
 
     {
         let builder = builder.clone();
-        // NOTE: Implement the session/system bus button
+        let notifier = notifier.clone();
+        // NOTE: Implement the session/system bus button, which returns to the local machine's managers
         session_switch.connect_state_set(move |switch, enabled| {
-            refresh_all_units(
-                if enabled {
-                    BusType::Session
-                } else {
-                    BusType::System
-                },
-                &builder,
-            );
+            let bustype = if enabled { BusType::Session } else { BusType::System };
+            switch_manager(Arc::new(LocalBackend::new(bustype)), false, &builder, &store, &details, &notifier,
+                &busy);
             switch.set_state(enabled);
             gtk::Inhibit(true)
         });
@@ -524,9 +908,21 @@ pub fn launch() {
     });
 
     // Define custom actions on keypress
+    let unit_search: gtk::SearchEntry = builder.get_object("unit_search").unwrap();
     window.connect_key_press_event(move |_, key| {
-        if let key::Escape = key.get_keyval() {
-            gtk::main_quit()
+        match key.get_keyval() {
+            // Escape clears the search while it is being typed, rather than quitting the program.
+            key::Escape if unit_search.has_focus() => {
+                unit_search.set_text("");
+                return gtk::Inhibit(true)
+            }
+            key::Escape => gtk::main_quit(),
+            // Ctrl+F focuses the search entry of the unit lists.
+            key::f if key.get_state().contains(gdk::CONTROL_MASK) => {
+                unit_search.grab_focus();
+                return gtk::Inhibit(true)
+            }
+            _ => (),
         }
         gtk::Inhibit(false)
     });
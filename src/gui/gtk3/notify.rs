@@ -0,0 +1,185 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use gtk::*;
+
+/// The outcome of an operation, as it is recorded in the session's activity log.
+#[derive(Clone, Debug)]
+pub struct Activity {
+    pub time: String,
+    pub succeeded: bool,
+    pub message: String,
+    pub details: String,
+}
+
+/// Displays the outcome of operations in the notification bar above the main window's stack, and records each
+/// outcome in an activity log that lasts for the duration of the session.
+#[derive(Clone)]
+pub struct Notifier {
+    bar: InfoBar,
+    label: Label,
+    details_button: Button,
+    retry_button: Button,
+    window: Window,
+    details: Rc<RefCell<String>>,
+    retry: Rc<RefCell<Option<Rc<dyn Fn()>>>>,
+    log: Rc<RefCell<Vec<Activity>>>,
+}
+
+impl Notifier {
+    /// Obtains the notification widgets from the builder and programs their signals.
+    pub fn new(builder: &Builder) -> Notifier {
+        let notifier = Notifier {
+            bar: builder.get_object("notification_bar").unwrap(),
+            label: builder.get_object("notification_label").unwrap(),
+            details_button: builder.get_object("notification_details").unwrap(),
+            retry_button: builder.get_object("notification_retry").unwrap(),
+            window: builder.get_object("main_window").unwrap(),
+            details: Rc::new(RefCell::new(String::new())),
+            retry: Rc::new(RefCell::new(None)),
+            log: Rc::new(RefCell::new(Vec::new())),
+        };
+
+        // NOTE: Hide the notification when the close button is clicked.
+        notifier.bar.connect_response(|bar, _| bar.hide());
+
+        {
+            // NOTE: Display the details of the latest notification in a dialog.
+            let notifier = notifier.clone();
+            notifier.details_button.clone().connect_clicked(move |_| {
+                let details = notifier.details.borrow().clone();
+                show_text(&notifier.window, "Details", &details);
+            });
+        }
+
+        {
+            // NOTE: Perform the failed operation again.
+            let notifier = notifier.clone();
+            notifier.retry_button.clone().connect_clicked(move |_| {
+                notifier.bar.hide();
+                // The action is cloned out of the cell, as it may replace itself if it fails again.
+                let retry = notifier.retry.borrow().clone();
+                if let Some(retry) = retry {
+                    retry();
+                }
+            });
+        }
+
+        {
+            // NOTE: Open the activity log from the systemd menu.
+            let notifier = notifier.clone();
+            let activity_log_button: Button = builder.get_object("activity_log_button").unwrap();
+            let popover: PopoverMenu = builder.get_object("systemd_menu_popover").unwrap();
+            activity_log_button.connect_clicked(move |_| {
+                popover.set_visible(false);
+                notifier.show_log();
+            });
+        }
+
+        notifier
+    }
+
+    /// Notifies the user that an operation has succeeded.
+    pub fn success(&self, message: String) {
+        self.notify(true, message, String::new(), None);
+    }
+
+    /// Notifies the user that an operation has failed, with the details of the failure.
+    pub fn failure(&self, message: String, details: String) {
+        self.notify(false, message, details, None);
+    }
+
+    /// Notifies the user that an operation has failed, offering to perform the operation again.
+    pub fn failure_with_retry<F: Fn() + 'static>(&self, message: String, details: String, retry: F) {
+        self.notify(false, message, details, Some(Rc::new(retry)));
+    }
+
+    /// Records the outcome in the activity log and displays it in the notification bar.
+    fn notify(&self, succeeded: bool, message: String, details: String, retry: Option<Rc<dyn Fn()>>) {
+        self.log.borrow_mut().push(Activity {
+            time: format_time(SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0)),
+            succeeded: succeeded,
+            message: message.clone(),
+            details: details.clone(),
+        });
+
+        self.bar.set_message_type(if succeeded { MessageType::Info } else { MessageType::Error });
+        self.label.set_text(&message);
+        self.details_button.set_visible(!details.is_empty());
+        self.retry_button.set_visible(retry.is_some());
+        *self.details.borrow_mut() = details;
+        *self.retry.borrow_mut() = retry;
+        self.bar.show();
+    }
+
+    /// Displays every outcome that has been recorded in this session within a dialog.
+    pub fn show_log(&self) {
+        let dialog = Dialog::new_with_buttons(Some("Activity Log"), Some(&self.window),
+            DIALOG_MODAL | DIALOG_DESTROY_WITH_PARENT, &[("Close", ResponseType::Close.into())]);
+        dialog.set_default_size(700, 400);
+
+        // The `ListStore` contains the time, outcome, message and details of each activity.
+        let store = ListStore::new(&[Type::String, Type::String, Type::String, Type::String]);
+        let tree = TreeView::new_with_model(&store);
+        for &(title, id) in &[("Time", 0), ("Outcome", 1), ("Message", 2), ("Details", 3)] {
+            let column = TreeViewColumn::new();
+            let renderer = CellRendererText::new();
+            column.set_title(title);
+            column.set_resizable(true);
+            column.pack_start(&renderer, true);
+            column.add_attribute(&renderer, "text", id);
+            tree.append_column(&column);
+        }
+
+        // The most recent activity is listed first.
+        for activity in self.log.borrow().iter().rev() {
+            let outcome = if activity.succeeded { "Succeeded" } else { "Failed" };
+            store.insert_with_values(None, &[0, 1, 2, 3],
+                &[&activity.time, &outcome, &activity.message, &activity.details]);
+        }
+
+        let scrolled = ScrolledWindow::new(None, None);
+        scrolled.set_shadow_type(ShadowType::In);
+        scrolled.add(&tree);
+        let content = dialog.get_content_area();
+        content.pack_start(&scrolled, true, true, 0);
+        content.show_all();
+
+        dialog.run();
+        dialog.destroy();
+    }
+}
+
+/// Displays a block of text within a dialog.
+pub fn show_text(window: &Window, title: &str, text: &str) {
+    let dialog = Dialog::new_with_buttons(Some(title), Some(window),
+        DIALOG_MODAL | DIALOG_DESTROY_WITH_PARENT, &[("Close", ResponseType::Close.into())]);
+    dialog.set_default_size(600, 300);
+
+    let view = TextView::new();
+    view.set_editable(false);
+    view.set_monospace(true);
+    view.set_wrap_mode(WrapMode::WordChar);
+    view.get_buffer().map(|buffer| buffer.set_text(text));
+    let scrolled = ScrolledWindow::new(None, None);
+    scrolled.set_shadow_type(ShadowType::In);
+    scrolled.add(&view);
+    let content = dialog.get_content_area();
+    content.pack_start(&scrolled, true, true, 0);
+    content.show_all();
+
+    dialog.run();
+    dialog.destroy();
+}
+
+/// Formats the number of seconds since the UNIX epoch as the UTC time of day, such as `14:03:09`.
+fn format_time(seconds: u64) -> String {
+    let seconds = seconds % 86400;
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60)
+}
+
+#[test]
+fn test_format_time() {
+    assert_eq!(format_time(0), "00:00:00");
+    assert_eq!(format_time(1476799389), "14:03:09");
+}
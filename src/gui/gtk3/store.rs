@@ -0,0 +1,210 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::mem;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, TryRecvError};
+use gtk::{self, ListStore, TreeIter};
+use crate::systemd::backend::SystemdBackend;
+use crate::systemd::resources::UnitResources;
+use crate::systemd::{self, SystemdUnit};
+use super::notify::Notifier;
+use super::units;
+use super::worker;
+
+/// The interval, in milliseconds, at which the units that changed outside of the application are reloaded.
+const WATCH_INTERVAL: u32 = 500;
+
+/// The interval, in milliseconds, at which the time since each unit's last state change is brought up to date.
+const ELAPSED_INTERVAL: u32 = 1000;
+
+/// The row that displays a unit in the model of its unit list.
+#[derive(Clone)]
+pub struct UnitRow {
+    pub model: ListStore,
+    pub iter: TreeIter,
+}
+
+/// The backend of the current manager, and its units keyed by their `SystemdUnit::key`, along with the rows that
+/// display them. Every view queries the store for the current state of a unit, and the store is updated as each
+/// action completes, so that the units only have to be listed again when the manager is switched.
+#[derive(Clone)]
+pub struct UnitStore {
+    backend: Rc<RefCell<Arc<dyn SystemdBackend>>>,
+    /// Counts the switches between managers, which identifies the current manager.
+    generation: Rc<Cell<u64>>,
+    units: Rc<RefCell<BTreeMap<String, SystemdUnit>>>,
+    rows: Rc<RefCell<HashMap<String, UnitRow>>>,
+    /// Set once the manager that is being watched is no longer the current manager.
+    watching: Rc<RefCell<Arc<AtomicBool>>>,
+}
+
+impl UnitStore {
+    pub fn new(backend: Arc<dyn SystemdBackend>) -> UnitStore {
+        UnitStore {
+            backend: Rc::new(RefCell::new(backend)),
+            generation: Rc::new(Cell::new(0)),
+            units: Rc::new(RefCell::new(BTreeMap::new())),
+            rows: Rc::new(RefCell::new(HashMap::new())),
+            watching: Rc::new(RefCell::new(Arc::new(AtomicBool::new(false)))),
+        }
+    }
+
+    /// Obtains the backend of the current manager, which may be moved to a background thread.
+    pub fn backend(&self) -> Arc<dyn SystemdBackend> {
+        self.backend.borrow().clone()
+    }
+
+    /// Switches to another manager. The units of the previous manager remain until they are replaced.
+    pub fn set_backend(&self, backend: Arc<dyn SystemdBackend>) {
+        *self.backend.borrow_mut() = backend;
+        self.generation.set(self.generation.get() + 1);
+    }
+
+    /// Identifies the current manager. It changes whenever the manager is switched, so that the results of
+    /// background work upon a previous manager, whose units may share the keys of the current manager's units, can be
+    /// discarded.
+    pub fn generation(&self) -> u64 {
+        self.generation.get()
+    }
+
+    /// Keeps the time since each unit's last state change up to date in the rows, for as long as the program runs.
+    pub fn follow_elapsed(&self) {
+        let store = self.clone();
+        gtk::timeout_add(ELAPSED_INTERVAL, move || {
+            store.update_elapsed();
+            gtk::Continue(true)
+        });
+    }
+
+    /// Watches the current manager in the background, and reloads each unit that it reports a change to, so that
+    /// units which are started or stopped outside of the application are brought up to date. The changes that are
+    /// reported within an interval are gathered, so that each unit is only reloaded once. The manager that was
+    /// previously watched is no longer watched, and a manager that can no longer be watched is reported through the
+    /// notifier.
+    pub fn watch(&self, notifier: &Notifier) {
+        let stop = Arc::new(AtomicBool::new(false));
+        mem::replace(&mut *self.watching.borrow_mut(), stop.clone()).store(true, Ordering::Relaxed);
+
+        let (sender, receiver) = mpsc::channel();
+        let (backend, watcher_stop, failure_stop) = (self.backend(), stop.clone(), stop.clone());
+        let (name, notifier) = (backend.name(), notifier.clone());
+        worker::spawn(move || backend.watch(sender, &watcher_stop), move |result| {
+            // The failure is not reported once the manager has been switched, as it is no longer being displayed.
+            if let (Err(why), false) = (result, failure_stop.load(Ordering::Relaxed)) {
+                notifier.failure(format!("unable to watch {}, so live updates are off", name), why.to_string());
+            }
+        });
+
+        let store = self.clone();
+        gtk::timeout_add(WATCH_INTERVAL, move || {
+            let mut changed = BTreeSet::new();
+            let watching = loop {
+                match receiver.try_recv() {
+                    Ok((bustype, name)) => { changed.insert(systemd::unit_key(bustype, &name)); }
+                    Err(TryRecvError::Empty) => break true,
+                    Err(TryRecvError::Disconnected) => break false,
+                }
+            };
+            if stop.load(Ordering::Relaxed) {
+                return gtk::Continue(false)
+            }
+
+            for unit in changed.iter().filter_map(|key| store.get(key)) {
+                let (backend, store, stop) = (store.backend(), store.clone(), stop.clone());
+                worker::spawn(move || backend.reload_unit(&unit), move |reloaded| {
+                    // The unit is discarded if the manager has been switched while it was reloaded.
+                    if let (Ok(reloaded), false) = (reloaded, stop.load(Ordering::Relaxed)) {
+                        store.update(reloaded);
+                    }
+                });
+            }
+            gtk::Continue(watching)
+        });
+    }
+
+    /// Replaces the contents of the store with the units of a newly-listed bus.
+    pub fn replace(&self, units: &[SystemdUnit]) {
+        self.rows.borrow_mut().clear();
+        *self.units.borrow_mut() = units.iter().map(|unit| (unit.key(), unit.clone())).collect();
+    }
+
+    /// Obtains the current state of the unit with the given key.
+    pub fn get(&self, key: &str) -> Option<SystemdUnit> {
+        self.units.borrow().get(key).cloned()
+    }
+
+    /// Associates a row with the unit that it displays.
+    pub fn set_row(&self, key: &str, row: UnitRow) {
+        self.rows.borrow_mut().insert(key.to_owned(), row);
+    }
+
+    /// Records the new state of a unit, and updates its row to match. The unit is recorded before its row is
+    /// updated, as the row is filtered by the recorded state.
+    pub fn update(&self, unit: SystemdUnit) {
+        let key = unit.key();
+        self.units.borrow_mut().insert(key.clone(), unit.clone());
+        if let Some(row) = self.rows.borrow().get(&key) {
+            units::update_row(&row.model, &row.iter, &unit);
+        }
+    }
+
+    /// Records the new state of a unit that was collected from the manager of the given generation, unless the
+    /// manager has been switched since.
+    pub fn update_current(&self, generation: u64, unit: SystemdUnit) {
+        if generation == self.generation() {
+            self.update(unit);
+        }
+    }
+
+    /// Brings the time since each unit's last state change up to date in its row.
+    pub fn update_elapsed(&self) {
+        // The rows are collected first, as updating a row may filter it, which queries the store.
+        let rows: Vec<UnitRow> = self.rows.borrow().values().cloned().collect();
+        for row in rows {
+            units::update_elapsed(&row.model, &row.iter);
+        }
+    }
+
+    /// Displays the resources of a unit in its row, and records when the unit last changed its state.
+    pub fn update_resources(&self, key: &str, resources: &UnitResources) {
+        if let (Some(unit), Some(_)) = (self.units.borrow_mut().get_mut(key), resources.state_change) {
+            unit.state_change = resources.state_change;
+        }
+        if let Some(row) = self.rows.borrow().get(key) {
+            units::update_resources(&row.model, &row.iter, resources);
+        }
+    }
+
+    /// Modifies the stored state of the unit with the given key, if it exists.
+    pub fn modify<F: FnOnce(&mut SystemdUnit)>(&self, key: &str, modify: F) {
+        if let Some(mut unit) = self.get(key) {
+            modify(&mut unit);
+            self.update(unit);
+        }
+    }
+}
+
+#[test]
+fn test_unit_store() {
+    use crate::systemd::{ActiveState, UnitState};
+    use crate::systemd::backend::FakeBackend;
+
+    let store = UnitStore::new(Arc::new(FakeBackend::default()));
+    store.replace(&[SystemdUnit::loaded("sshd.service", "OpenSSH Daemon", UnitState::Disabled, ActiveState::Inactive)]);
+    store.modify("system/sshd.service", |unit| unit.state = UnitState::Enabled);
+    store.modify("system/cups.service", |unit| unit.state = UnitState::Enabled);
+    assert_eq!(store.get("system/sshd.service").map(|unit| unit.state), Some(UnitState::Enabled));
+    assert!(store.get("system/cups.service").is_none());
+    // The unit of the same name on the session bus is a different unit.
+    assert!(store.get("user/sshd.service").is_none());
+
+    // The units that were collected before the manager was switched are discarded.
+    let generation = store.generation();
+    let mut stale = store.get("system/sshd.service").unwrap();
+    stale.state = UnitState::Masked;
+    store.set_backend(Arc::new(FakeBackend::default()));
+    store.update_current(generation, stale);
+    assert_eq!(store.get("system/sshd.service").map(|unit| unit.state), Some(UnitState::Enabled));
+}
@@ -1,54 +1,391 @@
-use gtk::{Box, Image, Label, ListBox, ListBoxRow, Orientation};
-use gtk::prelude::*;
-use crate::systemd::{self, UnitState, SystemdUnit};
-use crate::systemd::systemctl::Systemctl;
+use gtk::*;
+use crate::systemd::{self, ActiveState, UnitState, SystemdUnit};
+use crate::systemd::backend::SystemdBackend;
+use crate::systemd::resources::{self, UnitResources};
+use std::cell::RefCell;
 use std::path::Path;
+use std::rc::Rc;
+use super::store::{UnitRow, UnitStore};
 
-/// Create a `gtk::ListboxRow` and add it to the `gtk::ListBox`, and then add the `Image` to a vector so that we can later modify
-/// it when the state changes.
-pub fn create_row(row: &mut ListBoxRow, unit: &SystemdUnit, active_icons: &mut Vec<Image>, enable_icons: &mut Vec<Image>) {
-    // Creates the status icons used by the rows.
-    fn get_icon(state: bool, tooltip: &str) -> Image {
-        let icon = if state { Image::new_from_stock("gtk-yes", 4) } else { Image::new_from_stock("gtk-no", 4) };
-        icon.set_tooltip_text(Some(tooltip));
-        icon
+/// The details of a unit that are displayed in the right pane, which are collected on a background thread.
+pub struct UnitDetails {
+    pub unit: SystemdUnit,
+    pub description: Option<String>,
+    pub info: String,
+    /// Whether the unit file could be read through the backend, and may therefore be saved through it.
+    pub editable: bool,
+    pub dependencies: String,
+    pub journal: String,
+}
+
+impl UnitDetails {
+    /// Collects the unit file, dependencies, journal, and current state of the given unit from the backend.
+    pub fn collect(backend: &dyn SystemdBackend, unit: &SystemdUnit) -> UnitDetails {
+        // Obtain information from the unit's file.
+        let file = backend.unit_file(unit);
+        let editable = file.is_ok();
+        let info = file.unwrap_or_default();
+        UnitDetails {
+            // The previously-known state is displayed if the state could not be reloaded.
+            unit: backend.reload_unit(unit).unwrap_or_else(|_| unit.clone()),
+            description: systemd::get_unit_description(&info).map(String::from),
+            dependencies: backend.dependencies(unit).unwrap_or_else(|why| why.to_string()),
+            journal: backend.journal(&unit.journal_query())
+                .unwrap_or_else(|why| format!("Unable to read the journal entry for {}: {}", unit.name, why)),
+            info: info,
+            editable: editable,
+        }
     }
 
-    // Create the unit label with the extension removed.
-    let unit_label = Label::new(Some(Path::new(&unit.name).file_stem().unwrap().to_str().unwrap()));
-    unit_label.set_tooltip_text(systemd::get_unit_description(unit.get_info().as_str()));
+    pub fn enabled(&self) -> bool { self.unit.state == UnitState::Enabled }
 
-    // Create the running and enable status icons.
-    let running = get_icon(unit.is_active(), "Active Status");
-    let enabled = get_icon(unit.state == UnitState::Enabled, "Enablement Status");
+    pub fn active(&self) -> bool { self.unit.active_state == ActiveState::Active }
+}
 
-    // Create a horizontal box that contains the unit label, running status, and enablement status.
-    let unit_box = Box::new(Orientation::Horizontal, 0);
-    unit_box.pack_start(&unit_label, false, false, 5);
-    unit_box.pack_end(&running, false, false, 0);
-    unit_box.pack_end(&enabled, false, false, 0);
+/// The columns of the unit lists' model. Each numeric column is displayed through the text column that follows it,
+/// and is sorted by the numeric column.
+pub mod column {
+    pub const KEY: u32 = 0;
+    pub const NAME: u32 = 1;
+    pub const DESCRIPTION: u32 = 2;
+    pub const LOAD: u32 = 3;
+    pub const ACTIVE: u32 = 4;
+    pub const SUB: u32 = 5;
+    pub const ENABLEMENT: u32 = 6;
+    pub const BUS: u32 = 7;
+    pub const STATE_CHANGE: u32 = 8;
+    pub const STATE_CHANGE_TEXT: u32 = 9;
+    pub const MEMORY: u32 = 10;
+    pub const MEMORY_TEXT: u32 = 11;
+    pub const CPU: u32 = 12;
+    pub const CPU_TEXT: u32 = 13;
+    pub const ACTIVE_ICON: u32 = 14;
+    pub const ACTIVE_COLOR: u32 = 15;
+    pub const ENABLEMENT_ICON: u32 = 16;
+    pub const ENABLEMENT_COLOR: u32 = 17;
+    pub const TOOLTIP: u32 = 18;
+}
 
-    // Add the box as a row in the `ListBox`.
-    row.add(&unit_box);
+/// Creates the model and columns of a unit list. The rows of the model are filtered by the unit filter, and sorted
+/// by the column whose header was clicked last, which is initially the name. Hovering over a row displays the
+/// unit's sub-state and how long the unit has been in it.
+pub fn setup_list(tree: &TreeView, store: &UnitStore, filter: &Rc<RefCell<UnitFilter>>) {
+    let model = ListStore::new(&[Type::String, Type::String, Type::String, Type::String, Type::String, Type::String,
+        Type::String, Type::String, Type::U64, Type::String, Type::U64, Type::String, Type::U64, Type::String,
+        Type::String, Type::String, Type::String, Type::String, Type::String]);
 
-    // Add the icons to the mutable input vectors so that they can be modified by later actions.
-    active_icons.push(running);
-    enable_icons.push(enabled);
+    // The row of a unit is displayed if the unit matches the filter.
+    let filtered = TreeModelFilter::new(&model, None);
+    {
+        let (store, filter) = (store.clone(), filter.clone());
+        filtered.set_visible_func(move |model, iter| {
+            model.get_value(iter, column::KEY as i32).get::<String>()
+                .and_then(|key| store.get(&key))
+                .map_or(true, |unit| filter.borrow().matches(&unit))
+        });
+    }
+    let sorted = TreeModelSort::new(&filtered);
+
+    // A simple macro for adding a column that displays the text of one model column, and is sorted by another.
+    macro_rules! add_column {
+        ($title:expr, $text:expr, $sort:expr) => {{
+            let column   = TreeViewColumn::new();
+            let renderer = CellRendererText::new();
+            column.set_title($title);
+            column.set_resizable(true);
+            column.set_sort_column_id($sort as i32);
+            column.pack_start(&renderer, true);
+            column.add_attribute(&renderer, "text", $text as i32);
+            tree.append_column(&column);
+            column
+        }}
+    }
+
+    // A state is displayed with an icon before its name, and both are colored by the state.
+    macro_rules! add_state_column {
+        ($title:expr, $text:expr, $icon:expr, $color:expr) => {{
+            let column   = TreeViewColumn::new();
+            let icon     = CellRendererPixbuf::new();
+            let renderer = CellRendererText::new();
+            column.set_title($title);
+            column.set_resizable(true);
+            column.set_sort_column_id($text as i32);
+            column.pack_start(&icon, false);
+            column.pack_start(&renderer, true);
+            column.add_attribute(&icon, "icon-name", $icon as i32);
+            column.add_attribute(&renderer, "text", $text as i32);
+            column.add_attribute(&renderer, "foreground", $color as i32);
+            tree.append_column(&column);
+        }}
+    }
+
+    add_column!("Name", column::NAME, column::NAME);
+    add_column!("Description", column::DESCRIPTION, column::DESCRIPTION);
+    add_column!("Load", column::LOAD, column::LOAD);
+    add_state_column!("Active", column::ACTIVE, column::ACTIVE_ICON, column::ACTIVE_COLOR);
+    add_column!("Sub", column::SUB, column::SUB);
+    add_state_column!("Enablement", column::ENABLEMENT, column::ENABLEMENT_ICON, column::ENABLEMENT_COLOR);
+    // The bus is only displayed when the units of both buses are listed together.
+    add_column!("Bus", column::BUS, column::BUS).set_visible(false);
+    add_column!("Changed", column::STATE_CHANGE_TEXT, column::STATE_CHANGE);
+    add_column!("Memory", column::MEMORY_TEXT, column::MEMORY);
+    add_column!("CPU", column::CPU_TEXT, column::CPU);
+
+    sorted.set_sort_column_id(column::NAME as i32, SortType::Ascending);
+    tree.set_model(Some(&sorted));
+    tree.set_tooltip_column(column::TOOLTIP as i32);
+}
+
+/// Obtains the filter beneath the sorting of a unit list, and the model of its rows.
+fn get_models(tree: &TreeView) -> Option<(TreeModelFilter, ListStore)> {
+    let sorted = tree.get_model()?.downcast::<TreeModelSort>().ok()?;
+    let filtered = sorted.get_model()?.downcast::<TreeModelFilter>().ok()?;
+    let model = filtered.get_model()?.downcast::<ListStore>().ok()?;
+    Some((filtered, model))
 }
 
-/// Obtains the index of the currently-selected row, else returns the default of 0.
-fn get_selected_row(list: &ListBox) -> usize {
-    list.get_selected_row().map_or(0, |row| row.get_index() as usize)
+/// Adds a row for the unit to the model, and records the row in the store so that it can later be modified when the
+/// state of the unit changes.
+pub fn insert_row(model: &ListStore, unit: &SystemdUnit, store: &UnitStore) {
+    let name = Path::new(&unit.name).file_stem().and_then(|stem| stem.to_str()).unwrap_or(&unit.name).to_owned();
+    let iter = model.insert_with_values(None, &[column::KEY, column::NAME, column::BUS, column::STATE_CHANGE,
+        column::MEMORY, column::CPU], &[&unit.key(), &name, &unit.bus_label(), &0u64, &0u64, &0u64]);
+    update_row(model, &iter, unit);
+    store.set_row(&unit.key(), UnitRow { model: model.clone(), iter: iter });
+}
+
+/// Obtains the symbolic icon and the color that represent a runtime state, matching the markers of the terminal
+/// interface: green while running, yellow while changing, and red once failed.
+fn active_style(state: &ActiveState) -> (&'static str, &'static str) {
+    match *state {
+        ActiveState::Active => ("media-playback-start-symbolic", "#26a269"),
+        ActiveState::Reloading => ("view-refresh-symbolic", "#26a269"),
+        ActiveState::Activating => ("go-up-symbolic", "#c88800"),
+        ActiveState::Deactivating => ("go-down-symbolic", "#c88800"),
+        ActiveState::Failed => ("dialog-error-symbolic", "#c01c28"),
+        ActiveState::Inactive => ("media-playback-stop-symbolic", "#77767b"),
+    }
+}
+
+/// Obtains the symbolic icon and the color that represent an enablement state.
+fn enablement_style(state: &UnitState) -> (&'static str, &'static str) {
+    match *state {
+        UnitState::Enabled => ("emblem-ok-symbolic", "#26a269"),
+        UnitState::Indirect | UnitState::Linked | UnitState::Alias => ("insert-link-symbolic", "#1c71d8"),
+        UnitState::Generated | UnitState::Transient => ("system-run-symbolic", "#1c71d8"),
+        UnitState::Static => ("changes-prevent-symbolic", "#77767b"),
+        UnitState::Disabled => ("list-remove-symbolic", "#77767b"),
+        UnitState::Masked => ("action-unavailable-symbolic", "#c01c28"),
+        UnitState::Bad => ("dialog-warning-symbolic", "#c01c28"),
+    }
+}
+
+/// Describes the sub-state of a unit and how long the unit has been in it, such as `running for 2h 5min`. The
+/// time of the last state change is in microseconds since the Unix epoch, and is zero if it is unknown.
+fn describe_state(sub_state: &str, state_change: u64) -> String {
+    if state_change == 0 {
+        sub_state.to_owned()
+    } else {
+        format!("{} for {}", sub_state, resources::format_elapsed(state_change))
+    }
+}
+
+/// Updates the tooltip of the unit's row from the sub-state and time of the last state change in the row.
+fn update_tooltip(model: &ListStore, iter: &TreeIter) {
+    let sub_state = model.get_value(iter, column::SUB as i32).get::<String>().unwrap_or_default();
+    let state_change = model.get_value(iter, column::STATE_CHANGE as i32).get::<u64>().unwrap_or(0);
+    set_changed_text(model, iter, column::TOOLTIP, describe_state(&sub_state, state_change));
+}
+
+/// Sets a text column of the row, unless it already holds the text, so that rows which are brought up to date
+/// without changing are not redrawn or filtered again.
+fn set_changed_text(model: &ListStore, iter: &TreeIter, column: u32, text: String) {
+    if model.get_value(iter, column as i32).get::<String>().as_ref() != Some(&text) {
+        model.set(iter, &[column], &[&text]);
+    }
+}
+
+/// Brings the time since the unit's last state change up to date in its row and tooltip, as the time is displayed
+/// relative to the current time.
+pub fn update_elapsed(model: &ListStore, iter: &TreeIter) {
+    let state_change = model.get_value(iter, column::STATE_CHANGE as i32).get::<u64>().unwrap_or(0);
+    if state_change != 0 {
+        set_changed_text(model, iter, column::STATE_CHANGE_TEXT, resources::format_since(state_change));
+        update_tooltip(model, iter);
+    }
+}
+
+/// Updates the description and states of the unit's row, along with the time of its last state change once the unit
+/// has been reloaded, so that the tooltip describes the current state.
+pub fn update_row(model: &ListStore, iter: &TreeIter, unit: &SystemdUnit) {
+    let (active_icon, active_color) = active_style(&unit.active_state);
+    let (enablement_icon, enablement_color) = enablement_style(&unit.state);
+    model.set(iter, &[column::DESCRIPTION, column::LOAD, column::ACTIVE, column::SUB, column::ENABLEMENT,
+        column::ACTIVE_ICON, column::ACTIVE_COLOR, column::ENABLEMENT_ICON, column::ENABLEMENT_COLOR],
+        &[&unit.description, &unit.load_state, &unit.active_state.as_str(), &unit.sub_state,
+            &unit.state.as_str(), &active_icon, &active_color, &enablement_icon, &enablement_color]);
+    if let Some(state_change) = unit.state_change {
+        model.set(iter, &[column::STATE_CHANGE, column::STATE_CHANGE_TEXT],
+            &[&state_change, &resources::format_since(state_change)]);
+    }
+    update_tooltip(model, iter);
+}
+
+/// Updates the time of the last state change and the resource usage of the unit's row. Values that are unknown
+/// are sorted as zero and displayed as empty.
+pub fn update_resources(model: &ListStore, iter: &TreeIter, resources: &UnitResources) {
+    let text = |value: Option<u64>, format: fn(u64) -> String| value.map(format).unwrap_or_default();
+    model.set(iter, &[column::STATE_CHANGE, column::STATE_CHANGE_TEXT, column::MEMORY, column::MEMORY_TEXT,
+        column::CPU, column::CPU_TEXT], &[
+            &resources.state_change.unwrap_or(0), &text(resources.state_change, resources::format_since),
+            &resources.memory.unwrap_or(0), &text(resources.memory, resources::format_bytes),
+            &resources.cpu.unwrap_or(0), &text(resources.cpu, resources::format_cpu),
+        ]);
+    update_tooltip(model, iter);
+}
+
+/// The search text and state filters that determine which units are displayed in the unit lists.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UnitFilter {
+    pub text: String,
+    pub active: bool,
+    pub inactive: bool,
+    pub failed: bool,
+    pub enabled: bool,
+    pub disabled: bool,
+    pub masked: bool,
+}
+
+impl Default for UnitFilter {
+    fn default() -> UnitFilter {
+        UnitFilter {
+            text: String::new(),
+            active: true,
+            inactive: true,
+            failed: true,
+            enabled: true,
+            disabled: true,
+            masked: true,
+        }
+    }
+}
+
+impl UnitFilter {
+    /// Whether the unit's name or description contains the search text, ignoring case, and whether its runtime
+    /// and enablement states are both displayed. Units that are starting, stopping, or reloading are considered
+    /// to be active.
+    pub fn matches(&self, unit: &SystemdUnit) -> bool {
+        let text = self.text.trim().to_lowercase();
+        let found = text.is_empty() || unit.name.to_lowercase().contains(&text)
+            || unit.description.to_lowercase().contains(&text);
+        let running = match unit.active_state {
+            ActiveState::Failed => self.failed,
+            ActiveState::Inactive => self.inactive,
+            _ => self.active,
+        };
+        let enablement = match unit.state {
+            UnitState::Enabled => self.enabled,
+            UnitState::Masked => self.masked,
+            _ => self.disabled,
+        };
+        found && running && enablement
+    }
+}
+
+/// The stack of unit lists, from which the currently-selected unit is obtained.
+#[derive(Clone)]
+pub struct UnitLists {
+    pub stack: Stack,
+    pub services: TreeView,
+    pub sockets: TreeView,
+    pub timers: TreeView,
+}
+
+impl UnitLists {
+    /// Obtains the lists of every unit type.
+    pub fn all(&self) -> [&TreeView; 3] { [&self.services, &self.sockets, &self.timers] }
+
+    /// Obtains the list that is currently visible.
+    pub fn visible(&self) -> Option<&TreeView> {
+        match self.stack.get_visible_child_name().as_ref().map(|name| name.as_str()) {
+            Some("Services") => Some(&self.services),
+            Some("Sockets") => Some(&self.sockets),
+            Some("Timers") => Some(&self.timers),
+            _ => None,
+        }
+    }
+
+    /// Obtains the model of the list's rows.
+    pub fn model(list: &TreeView) -> Option<ListStore> {
+        get_models(list).map(|(_, model)| model)
+    }
+
+    /// Filters the rows of every list again, after the unit filter has changed.
+    pub fn refilter(&self) {
+        for list in self.all().iter() {
+            get_models(list).map(|(filtered, _)| filtered.refilter());
+        }
+    }
+
+    /// Displays the bus of each unit when the units of both buses are listed together.
+    pub fn show_bus(&self, visible: bool) {
+        for list in self.all().iter() {
+            for bus in list.get_columns().iter().filter(|view| view.get_sort_column_id() == column::BUS as i32) {
+                bus.set_visible(visible);
+            }
+        }
+    }
+
+    /// Selects the first row of the list, which loads the details of its unit.
+    pub fn select_first(list: &TreeView) {
+        list.get_selection().select_path(&TreePath::new_first());
+    }
+
+    /// Obtains the current state of the units that are selected in the list, in the order that they are displayed.
+    pub fn all_selected_in(list: &TreeView, store: &UnitStore) -> Vec<SystemdUnit> {
+        let (paths, model) = list.get_selection().get_selected_rows();
+        paths.iter()
+            .filter_map(|path| model.get_iter(path))
+            .filter_map(|iter| model.get_value(&iter, column::KEY as i32).get::<String>())
+            .filter_map(|key| store.get(&key))
+            .collect()
+    }
+
+    /// Obtains the current state of the unit that is selected in the list. If several units are selected, the first
+    /// of them is the one whose details are displayed.
+    pub fn selected_in(list: &TreeView, store: &UnitStore) -> Option<SystemdUnit> {
+        UnitLists::all_selected_in(list, store).into_iter().next()
+    }
+
+    /// Obtains the current state of the unit that is selected in the visible list.
+    pub fn selected_unit(&self, store: &UnitStore) -> Option<SystemdUnit> {
+        self.visible().and_then(|list| UnitLists::selected_in(list, store))
+    }
+
+    /// Obtains the current state of every unit that is selected in the visible list.
+    pub fn selected_units(&self, store: &UnitStore) -> Vec<SystemdUnit> {
+        self.visible().map(|list| UnitLists::all_selected_in(list, store)).unwrap_or_default()
+    }
 }
 
-/// Obtains the currently-selected unit.
-pub fn get_current_unit<'a>(units: &'a [SystemdUnit], list: &ListBox) -> &'a SystemdUnit {
-    &units[get_selected_row(list)]
+#[test]
+fn test_unit_filter() {
+    let unit = SystemdUnit::loaded("sshd.service", "OpenSSH Daemon", UnitState::Enabled, ActiveState::Failed);
+    assert!(UnitFilter::default().matches(&unit));
+    assert!(UnitFilter { text: String::from("SSH"), ..UnitFilter::default() }.matches(&unit));
+    assert!(UnitFilter { text: String::from("daemon "), ..UnitFilter::default() }.matches(&unit));
+    assert!(!UnitFilter { text: String::from("cups"), ..UnitFilter::default() }.matches(&unit));
+    assert!(!UnitFilter { failed: false, ..UnitFilter::default() }.matches(&unit));
+    assert!(UnitFilter { active: false, inactive: false, ..UnitFilter::default() }.matches(&unit));
+    assert!(!UnitFilter { enabled: false, ..UnitFilter::default() }.matches(&unit));
 }
 
-/// Obtains the currently-selected unit and it's associated icon
-pub fn get_current_unit_icons<'a>(units: &'a [SystemdUnit], units_box: &ListBox, icons: &'a [Image])
-        -> (&'a SystemdUnit, &'a Image) {
-    let index = get_selected_row(units_box);
-    (&units[index], &icons[index])
+#[test]
+fn test_state_styles() {
+    assert_eq!(active_style(&ActiveState::Failed), ("dialog-error-symbolic", "#c01c28"));
+    assert_ne!(active_style(&ActiveState::Activating), active_style(&ActiveState::Inactive));
+    assert_eq!(enablement_style(&UnitState::Masked).0, "action-unavailable-symbolic");
+    assert_ne!(enablement_style(&UnitState::Masked), enablement_style(&UnitState::Disabled));
+    assert_eq!(describe_state("dead", 0), "dead");
+    assert!(describe_state("running", 1_500_000_000_000_000).starts_with("running for "));
 }
@@ -0,0 +1,93 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::mpsc::{self, TryRecvError};
+use std::thread;
+use gtk::prelude::*;
+use gtk::{self, Spinner, Widget};
+
+/// The interval, in milliseconds, at which the GTK main loop checks whether a background operation has finished.
+const POLL_INTERVAL: u32 = 50;
+
+/// Performs the blocking `work` on a background thread so that the window remains responsive. Once the work has
+/// completed, its result is sent back through a channel and handed to the `callback` on the GTK main thread. If the
+/// work panics, the callback is dropped without being called, which ends any `Operation` that it holds.
+pub fn spawn<T, W, C>(work: W, callback: C)
+    where T: Send + 'static,
+          W: FnOnce() -> T + Send + 'static,
+          C: FnOnce(T) + 'static
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        // The receiver will have been dropped if the application is quitting, so the error is ignored.
+        let _ = sender.send(work());
+    });
+
+    // The callback can only be called once, so it is taken out of the `Option` when the result arrives.
+    let mut callback = Some(callback);
+    gtk::timeout_add(POLL_INTERVAL, move || match receiver.try_recv() {
+        Ok(result) => {
+            if let Some(callback) = callback.take() {
+                callback(result);
+            }
+            gtk::Continue(false)
+        }
+        Err(TryRecvError::Empty) => gtk::Continue(true),
+        // The sender was dropped without a result, as the work panicked.
+        Err(TryRecvError::Disconnected) => {
+            callback.take();
+            gtk::Continue(false)
+        }
+    });
+}
+
+/// Displays a spinner and makes the controls it affects insensitive while background operations are in progress.
+#[derive(Clone)]
+pub struct Busy {
+    spinner: Spinner,
+    controls: Vec<Widget>,
+    operations: Rc<Cell<usize>>,
+}
+
+impl Busy {
+    pub fn new(spinner: Spinner, controls: Vec<Widget>) -> Busy {
+        Busy { spinner: spinner, controls: controls, operations: Rc::new(Cell::new(0)) }
+    }
+
+    /// Marks the beginning of an operation, which lasts until the returned `Operation` is ended or dropped.
+    pub fn begin(&self) -> Operation {
+        self.operations.set(self.operations.get() + 1);
+        self.spinner.start();
+        for control in &self.controls {
+            control.set_sensitive(false);
+        }
+        Operation { busy: self.clone() }
+    }
+
+    /// Marks the end of an operation. The controls are only made sensitive again once every operation has ended.
+    fn end(&self) {
+        let operations = self.operations.get().saturating_sub(1);
+        self.operations.set(operations);
+        if operations == 0 {
+            self.spinner.stop();
+            for control in &self.controls {
+                control.set_sensitive(true);
+            }
+        }
+    }
+}
+
+/// A background operation that is in progress. It is moved into the callback of the work, and ends when it is
+/// dropped, so that work which panicked does not leave the controls insensitive.
+#[must_use]
+pub struct Operation {
+    busy: Busy,
+}
+
+impl Operation {
+    /// Marks the end of the operation.
+    pub fn end(self) {}
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) { self.busy.end(); }
+}
@@ -0,0 +1,362 @@
+use std::io::{self, Write};
+use std::sync::Arc;
+use termion::{self, clear, color, cursor, style};
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::AlternateScreen;
+use crate::systemd::{self, ActiveState, SystemdUnit, UnitState};
+use crate::systemd::backend::{LocalBackend, SystemdBackend};
+use crate::systemd::dbus::dbus::BusType;
+use crate::systemd::journal::JournalQuery;
+
+/// The key bindings that are displayed in the footer of each view.
+const UNITS_HELP: &str = "Tab type  j/k move  Enter details  s start  x stop  e enable  d disable  \
+                          f/p/l file/deps/journal  u bus  1/2/3 view  q quit";
+const TEXT_HELP: &str = "j/k scroll  PgUp/PgDn page  r refresh  u bus  1/2/3 view  q quit";
+
+/// The views that are available from the number keys, matching the pages of the GTK front-end.
+#[derive(Clone, Copy, PartialEq)]
+enum View {
+    Units,
+    Journal,
+    Analyze,
+}
+
+/// The lists of units, which are cycled through with the Tab key.
+#[derive(Clone, Copy, PartialEq)]
+enum UnitList {
+    Services,
+    Sockets,
+    Timers,
+}
+
+impl UnitList {
+    fn title(&self) -> &'static str {
+        match *self {
+            UnitList::Services => "Services",
+            UnitList::Sockets => "Sockets",
+            UnitList::Timers => "Timers",
+        }
+    }
+
+    fn next(&self) -> UnitList {
+        match *self {
+            UnitList::Services => UnitList::Sockets,
+            UnitList::Sockets => UnitList::Timers,
+            UnitList::Timers => UnitList::Services,
+        }
+    }
+}
+
+/// The panes of the selected unit's details, which are chosen with the `f`, `p`, and `l` keys.
+#[derive(Clone, Copy, PartialEq)]
+enum Pane {
+    File,
+    Dependencies,
+    Journal,
+}
+
+/// The details of the selected unit, which are collected when the unit is opened.
+struct Details {
+    name: String,
+    info: String,
+    dependencies: String,
+    journal: String,
+}
+
+/// The state of the terminal interface.
+struct App {
+    backend: Arc<dyn SystemdBackend>,
+    bustype: BusType,
+    view: View,
+    list: UnitList,
+    units: Vec<SystemdUnit>,
+    selected: usize,
+    pane: Pane,
+    details: Option<Details>,
+    scroll: usize,
+    journal: String,
+    analyze: String,
+    message: String,
+}
+
+/// Runs the terminal interface until the user quits, managing the units of the given bus.
+pub fn launch(bustype: BusType) -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut screen = AlternateScreen::from(io::stdout().into_raw_mode()?);
+    write!(screen, "{}", cursor::Hide)?;
+
+    let mut app = App::new(bustype);
+    app.refresh_units();
+    app.draw(&mut screen)?;
+    for key in stdin.keys() {
+        match key? {
+            Key::Char('q') | Key::Esc | Key::Ctrl('c') => break,
+            key => app.handle(key),
+        }
+        app.draw(&mut screen)?;
+    }
+
+    write!(screen, "{}", cursor::Show)?;
+    screen.flush()
+}
+
+impl App {
+    fn new(bustype: BusType) -> App {
+        App {
+            backend: Arc::new(LocalBackend::new(bustype)),
+            bustype: bustype,
+            view: View::Units,
+            list: UnitList::Services,
+            units: Vec::new(),
+            selected: 0,
+            pane: Pane::File,
+            details: None,
+            scroll: 0,
+            journal: String::new(),
+            analyze: String::new(),
+            message: String::new(),
+        }
+    }
+
+    /// Lists the units of the current bus that belong to the current list.
+    fn refresh_units(&mut self) {
+        self.selected = 0;
+        self.details = None;
+        self.scroll = 0;
+        match self.backend.list_unit_files() {
+            Ok(units) => {
+                self.units = match self.list {
+                    UnitList::Services => systemd::collect_togglable_services(&units),
+                    UnitList::Sockets => systemd::collect_togglable_sockets(&units),
+                    UnitList::Timers => systemd::collect_togglable_timers(&units),
+                };
+            }
+            Err(why) => {
+                self.units.clear();
+                self.message = format!("unable to list the units of {}: {}", self.backend.name(), why);
+            }
+        }
+    }
+
+    /// Reads the journal of the current boot, newest entries first.
+    fn refresh_journal(&mut self) {
+        self.scroll = 0;
+        self.journal = self.backend.journal(&JournalQuery::boot(self.bustype))
+            .unwrap_or_else(|why| format!("Unable to read the journal: {}", why));
+    }
+
+    /// Collects the boot times and the time that each unit took to initialize, slowest first.
+    fn refresh_analyze(&mut self) {
+        self.scroll = 0;
+        let mut output = match self.backend.boot_time() {
+            Ok((kernel, userspace, total)) => {
+                format!("Kernel: {}    Userspace: {}    Total: {}\n\n", kernel, userspace, total)
+            }
+            Err(why) => format!("Unable to obtain the boot time: {}\n\n", why),
+        };
+        match self.backend.blame() {
+            Ok(units) => for unit in units.iter().rev() {
+                output.push_str(&format!("{:>8}ms  {}\n", unit.time, unit.service));
+            },
+            Err(why) => output.push_str(&format!("Unable to run systemd-analyze blame: {}\n", why)),
+        }
+        self.analyze = output;
+    }
+
+    /// Collects the unit file, dependencies, and journal of the selected unit.
+    fn open_selected(&mut self) {
+        self.scroll = 0;
+        let backend = &self.backend;
+        self.details = self.units.get(self.selected).map(|unit| Details {
+            name: unit.name.clone(),
+            info: backend.unit_file(unit).unwrap_or_else(|why| format!("Unable to read {}: {}", unit.path, why)),
+            dependencies: backend.dependencies(unit).unwrap_or_else(|why| why.to_string()),
+            journal: backend.journal(&unit.journal_query())
+                .unwrap_or_else(|why| format!("Unable to read the journal entry for {}: {}", unit.name, why)),
+        });
+    }
+
+    /// Performs the action upon the selected unit and reloads its state afterwards.
+    fn act<F>(&mut self, action: &str, perform: F)
+        where F: Fn(&dyn SystemdBackend, &SystemdUnit) -> Result<(), systemd::Error>
+    {
+        if let Some(unit) = self.units.get(self.selected).cloned() {
+            self.message = match perform(&*self.backend, &unit) {
+                Ok(()) => format!("{} has been {}", unit.name, action),
+                Err(why) => format!("{} could not be {}: {}", unit.name, action, why),
+            };
+            if let Ok(reloaded) = self.backend.reload_unit(&unit) {
+                self.units[self.selected] = reloaded;
+            }
+        }
+    }
+
+    fn handle(&mut self, key: Key) {
+        self.message.clear();
+        match key {
+            Key::Char('1') => self.view = View::Units,
+            Key::Char('2') => {
+                self.view = View::Journal;
+                self.refresh_journal();
+            }
+            Key::Char('3') => {
+                self.view = View::Analyze;
+                self.refresh_analyze();
+            }
+            Key::Char('u') => {
+                self.bustype = if self.bustype == BusType::Session { BusType::System } else { BusType::Session };
+                self.backend = Arc::new(LocalBackend::new(self.bustype));
+                self.refresh_units();
+                match self.view {
+                    View::Journal => self.refresh_journal(),
+                    View::Analyze => self.refresh_analyze(),
+                    View::Units => (),
+                }
+            }
+            Key::Char('r') => match self.view {
+                View::Units => self.refresh_units(),
+                View::Journal => self.refresh_journal(),
+                View::Analyze => self.refresh_analyze(),
+            },
+            Key::PageDown => self.scroll += page_size(),
+            Key::PageUp => self.scroll = self.scroll.saturating_sub(page_size()),
+            _ if self.view != View::Units => match key {
+                Key::Char('j') | Key::Down => self.scroll += 1,
+                Key::Char('k') | Key::Up => self.scroll = self.scroll.saturating_sub(1),
+                _ => (),
+            },
+            Key::Char('\t') => {
+                self.list = self.list.next();
+                self.refresh_units();
+            }
+            Key::Char('j') | Key::Down => {
+                if self.selected + 1 < self.units.len() {
+                    self.selected += 1;
+                }
+            }
+            Key::Char('k') | Key::Up => self.selected = self.selected.saturating_sub(1),
+            Key::Char('\n') => self.open_selected(),
+            Key::Char('f') => self.pane = Pane::File,
+            Key::Char('p') => self.pane = Pane::Dependencies,
+            Key::Char('l') => self.pane = Pane::Journal,
+            Key::Char('s') => self.act("started", |backend, unit| backend.start(unit)),
+            Key::Char('x') => self.act("stopped", |backend, unit| backend.stop(unit)),
+            Key::Char('e') => self.act("enabled", |backend, unit| backend.enable(unit).map(|_| ())),
+            Key::Char('d') => self.act("disabled", |backend, unit| backend.disable(unit).map(|_| ())),
+            _ => (),
+        }
+    }
+
+    fn draw<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let (width, height) = termion::terminal_size()?;
+        let (width, height) = (width as usize, height as usize);
+        write!(out, "{}", clear::All)?;
+
+        // The header lists the views, highlighting the current view, and the bus that is being managed.
+        let title = match self.view {
+            View::Units => format!("[1 Units: {}]  2 Journal  3 Analyze", self.list.title()),
+            View::Journal => String::from(" 1 Units  [2 Journal]  3 Analyze"),
+            View::Analyze => String::from(" 1 Units  2 Journal  [3 Analyze]"),
+        };
+        let header = format!("Systemd Manager  {}  ({})", title, self.backend.name());
+        write!(out, "{}{}{}{}", cursor::Goto(1, 1), style::Invert, fit(&header, width), style::Reset)?;
+
+        let rows = height.saturating_sub(2);
+        match self.view {
+            View::Units => self.draw_units(out, width, rows)?,
+            View::Journal => draw_text(out, &self.journal, self.scroll, (1, 2), width, rows)?,
+            View::Analyze => draw_text(out, &self.analyze, self.scroll, (1, 2), width, rows)?,
+        }
+
+        // The footer displays the outcome of the last action, or the key bindings if there was none.
+        let footer = if !self.message.is_empty() {
+            self.message.as_str()
+        } else if self.view == View::Units {
+            UNITS_HELP
+        } else {
+            TEXT_HELP
+        };
+        write!(out, "{}{}{}{}", cursor::Goto(1, height as u16), style::Invert, fit(footer, width), style::Reset)?;
+        out.flush()
+    }
+
+    /// Draws the unit list on the left, and the details of the opened unit on the right.
+    fn draw_units<W: Write>(&self, out: &mut W, width: usize, rows: usize) -> io::Result<()> {
+        let list_width = (width / 3).max(20).min(width);
+        // Scroll the list so that the selected unit remains visible.
+        let offset = if self.selected >= rows { self.selected + 1 - rows } else { 0 };
+        for (row, (index, unit)) in self.units.iter().enumerate().skip(offset).take(rows).enumerate() {
+            let (marker, marker_color) = match unit.active_state {
+                ActiveState::Active | ActiveState::Reloading => ("●", color::Fg(color::Green).to_string()),
+                ActiveState::Failed => ("●", color::Fg(color::Red).to_string()),
+                ActiveState::Activating | ActiveState::Deactivating => ("◐", color::Fg(color::Yellow).to_string()),
+                ActiveState::Inactive => ("○", color::Fg(color::Reset).to_string()),
+            };
+            let enabled = if unit.state == UnitState::Enabled { "enabled " } else { "disabled" };
+            let name = fit(&unit.name, list_width.saturating_sub(12));
+            write!(out, "{}", cursor::Goto(1, row as u16 + 2))?;
+            if index == self.selected {
+                write!(out, "{}", style::Invert)?;
+            }
+            write!(out, "{}{}{} {} {} ", marker_color, marker, color::Fg(color::Reset), name, enabled)?;
+            write!(out, "{}", style::Reset)?;
+        }
+
+        let (column, pane_width) = (list_width as u16 + 2, width.saturating_sub(list_width + 1));
+        match self.details {
+            Some(ref details) if self.units.get(self.selected).map_or(false, |unit| unit.name == details.name) => {
+                let unit = &self.units[self.selected];
+                let summary = format!("{} - {}: {} ({})", unit.name, unit.description,
+                    unit.active_state.as_str(), unit.sub_state);
+                let pane = match self.pane {
+                    Pane::File => "[f File]  p Dependencies  l Journal",
+                    Pane::Dependencies => " f File  [p Dependencies]  l Journal",
+                    Pane::Journal => " f File  p Dependencies  [l Journal]",
+                };
+                write!(out, "{}{}{}{}", cursor::Goto(column, 2), style::Bold, fit(&summary, pane_width), style::Reset)?;
+                write!(out, "{}{}", cursor::Goto(column, 3), fit(pane, pane_width))?;
+                let text = match self.pane {
+                    Pane::File => &details.info,
+                    Pane::Dependencies => &details.dependencies,
+                    Pane::Journal => &details.journal,
+                };
+                draw_text(out, text, self.scroll, (column, 4), pane_width, rows.saturating_sub(2))?;
+            }
+            _ => write!(out, "{}{}", cursor::Goto(column, 2), fit("Press Enter to open the selected unit.",
+                pane_width))?,
+        }
+        Ok(())
+    }
+}
+
+/// Draws the lines of the text that fit within the area whose top-left corner is at the given column and row,
+/// starting from the scrolled line.
+fn draw_text<W: Write>(out: &mut W, text: &str, scroll: usize, (column, top): (u16, u16), width: usize,
+        rows: usize) -> io::Result<()> {
+    for (row, line) in text.lines().skip(scroll).take(rows).enumerate() {
+        write!(out, "{}{}", cursor::Goto(column, top + row as u16), fit(line, width))?;
+    }
+    Ok(())
+}
+
+/// Truncates or pads the line so that it fills exactly the given number of columns.
+fn fit(line: &str, width: usize) -> String {
+    let mut output: String = line.chars().filter(|x| *x != '\t').take(width).collect();
+    let length = output.chars().count();
+    output.extend(::std::iter::repeat(' ').take(width - length));
+    output
+}
+
+/// The number of lines that the page keys scroll by.
+fn page_size() -> usize {
+    termion::terminal_size().map(|(_, height)| height as usize).unwrap_or(24).saturating_sub(4)
+}
+
+#[test]
+fn test_fit() {
+    assert_eq!(fit("sshd.service", 4), "sshd");
+    assert_eq!(fit("sshd", 6), "sshd  ");
+    assert_eq!(fit("a\tb", 3), "ab ");
+}
@@ -0,0 +1,35 @@
+//! Manages systemd units through systemd's D-Bus API, and reads from the commands that systemd provides, such as
+//! `systemctl`, `systemd-analyze`, and `journalctl`. This is the backend of the Systemd Manager application, which
+//! may also be used by other tools.
+//!
+//! ```no_run
+//! extern crate systemd_manager;
+//! use systemd_manager::{BusType, Dbus};
+//!
+//! fn main() {
+//!     // Start every enabled service of the user that is not already running.
+//!     for unit in systemd_manager::list_unit_files(BusType::Session).unwrap() {
+//!         if unit.state == systemd_manager::UnitState::Enabled
+//!                 && unit.active_state == systemd_manager::ActiveState::Inactive {
+//!             if let Err(why) = unit.start() {
+//!                 println!("unable to start {}: {}", unit.name, why);
+//!             }
+//!         }
+//!     }
+//! }
+//! ```
+
+#![warn(missing_docs)]
+
+pub mod systemd;
+
+pub use systemd::{ActiveState, Error, SystemdUnit, UnitState, UnitType};
+pub use systemd::analyze::Analyze;
+pub use systemd::backend::{AddressBackend, CombinedBackend, FakeBackend, LocalBackend, SystemdBackend};
+pub use systemd::dbus::{find_unit, list_unit_files, load_unit, reload_unit, Dbus};
+pub use systemd::dbus::dbus::BusType;
+pub use systemd::journal::{JournalFormat, JournalQuery};
+pub use systemd::logind::{LoggedInUser, UserBackend};
+pub use systemd::machined::{Machine, MachineBackend};
+pub use systemd::resources::UnitResources;
+pub use systemd::systemctl::Systemctl;
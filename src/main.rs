@@ -1,12 +1,40 @@
-extern crate gtk;
-extern crate gdk;
-extern crate quickersort;
+#[cfg(feature = "gui")] extern crate gtk;
+#[cfg(feature = "gui")] extern crate gdk;
+#[cfg(feature = "tui")] extern crate termion;
+extern crate systemd_manager;
 
-mod systemd;
+#[cfg(any(feature = "gui", feature = "tui"))]
+use systemd_manager::systemd;
+use std::env;
+use std::process;
+
+mod cli;
+#[cfg(any(feature = "gui", feature = "tui"))]
 mod gui {
+    #[cfg(feature = "gui")]
     pub mod gtk3;
+    #[cfg(feature = "tui")]
+    pub mod tui;
 }
 
 fn main() {
+    // The graphical interface is launched unless a command was given.
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        launch_gui();
+    } else {
+        process::exit(cli::run(&args));
+    }
+}
+
+#[cfg(feature = "gui")]
+fn launch_gui() {
     gui::gtk3::launch();
 }
+
+#[cfg(not(feature = "gui"))]
+fn launch_gui() {
+    eprintln!("systemd-manager: this build does not include the GUI, as it was built without the `gui` feature. \
+               Run `systemd-manager --help` for the command-line interface.");
+    process::exit(cli::EXIT_USAGE);
+}
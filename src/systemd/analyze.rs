@@ -1,29 +1,39 @@
+//! Measures the boot time of the system through `systemd-analyze`.
+
 use std::process::Command;
+use super::Error;
 
+/// The time that a unit took to initialize during boot, as reported by `systemd-analyze blame`.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Analyze {
+    /// The time that the unit took to initialize, in milliseconds.
     pub time: u32,
+    /// The name of the unit.
     pub service: String,
 }
 
 impl Analyze {
     /// Returns the results of `systemd-analyze blame` as a vector of `Analyze` units
-    pub fn blame() -> Option<Vec<Analyze>> {
-        Command::new("systemd-analyze").arg("blame").output().ok()
-            // Collect the standard output of the command as a `string` and pass it as an `Option`.
-            .and_then(|output| String::from_utf8(output.stdout).ok())
-            // Return a list of units and their times as an `Option`.
-            .and_then(|stdout| map_blames(stdout.as_str()))
+    pub fn blame() -> Result<Vec<Analyze>, Error> {
+        // Return a list of units and their times.
+        map_blames(run("blame")?.as_str())
+            .ok_or_else(|| Error::Parse(String::from("the output of systemd-analyze blame")))
     }
 
     /// Returns the results of `systemd-analyze time` as three `String` values (`kernel`, `userspace`, `total`)
-    pub fn time() -> (String, String, String) {
-        Command::new("systemd-analyze").arg("time").output().ok()
-            // Collect the standard output of the command as a `string` and pass it as an `Option`.
-            .and_then(|output| String::from_utf8(output.stdout).ok())
-            // Collect the values for `(kernel, userspace, total)`
-            .map_or(("N/A".to_owned(), "N/A".to_owned(), "N/A".to_owned()), |stdout| { map_times(stdout.as_str()) })
+    pub fn time() -> Result<(String, String, String), Error> {
+        // Collect the values for `(kernel, userspace, total)`
+        run("time").map(|stdout| map_times(stdout.as_str()))
+    }
+}
+
+/// Runs `systemd-analyze` with the given verb and collects its standard output as a `String`.
+fn run(verb: &str) -> Result<String, Error> {
+    let output = Command::new("systemd-analyze").arg(verb).output()?;
+    if !output.status.success() {
+        return Err(Error::JobFailed(String::from_utf8_lossy(&output.stderr).trim().to_owned()));
     }
+    String::from_utf8(output.stdout).map_err(|why| Error::Parse(why.to_string()))
 }
 
 /// Take the stdout of `systemd-analyze blame` and map the values to a vector of Analyze units.
@@ -73,7 +83,7 @@ fn parse_blame(x: &str) -> Option<Analyze> {
 
 }
 
-/// Parses a unit of a time in milliseconds
+/// Parses a unit of a time in milliseconds. Values in an unknown unit of measurement are skipped, and count as zero.
 fn parse_time(input: &str) -> u32 {
     if input.ends_with("ms") {
         input[0..input.len()-2].parse::<u32>().unwrap_or(0)
@@ -82,7 +92,6 @@ fn parse_time(input: &str) -> u32 {
     } else if input.ends_with("min") {
         input[0..input.len()-3].parse::<u32>().unwrap_or(0) * 60000u32
     } else {
-        println!("systemd-manager: unknown unit of measurement: {}", input);
         0u32
     }
 }
@@ -115,3 +124,9 @@ fn test_analyze_milliseconds() {
 fn test_analyze_garbage_input() {
     assert_eq!(None, parse_blame(""))
 }
+
+#[test]
+fn test_analyze_unknown_unit() {
+    let correct = Analyze{time: 1500, service: String::from("plymouth.service")};
+    assert_eq!(Some(correct), parse_blame("1.5s 20fortnights plymouth.service"));
+}
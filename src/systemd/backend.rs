@@ -0,0 +1,607 @@
+//! The transports through which a systemd manager is reached. Front-ends hold a `SystemdBackend` rather than a bus,
+//! so that the manager of the local machine, a manager at an arbitrary D-Bus address, or an in-memory fake may be
+//! managed alike.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+use super::analyze::Analyze;
+use super::connection::Bus;
+use super::dbus;
+use super::dbus::dbus::{BusType, MessageItem};
+use super::journal::JournalQuery;
+use super::systemctl::Systemctl;
+use super::{ActiveState, Error, SystemdUnit, UnitState};
+
+/// The operations that the front-ends perform upon a systemd manager.
+pub trait SystemdBackend: Send + Sync {
+    /// Describes the bus that the manager is reached through, such as `the system bus`.
+    fn name(&self) -> String;
+    /// Lists the unit files of the manager, along with their enablement and runtime states.
+    fn list_unit_files(&self) -> Result<Vec<SystemdUnit>, Error>;
+    /// Lists the unit files that could be reached, along with the error of any bus whose units could not be listed, so
+    /// that a manager which is unavailable does not hide the units of the others.
+    fn list_available_unit_files(&self) -> Result<(Vec<SystemdUnit>, Option<Error>), Error> {
+        self.list_unit_files().map(|units| (units, None))
+    }
+    /// Collects the current description, enablement, and runtime state of a single unit.
+    fn reload_unit(&self, unit: &SystemdUnit) -> Result<SystemdUnit, Error>;
+    /// Obtains the properties of the unit that belong to the given interface, such as
+    /// `org.freedesktop.systemd1.Unit`.
+    fn unit_properties(&self, unit: &SystemdUnit, interface: &str) -> Result<BTreeMap<String, MessageItem>, Error>;
+    /// Enables the unit file, returning true if it was already enabled.
+    fn enable(&self, unit: &SystemdUnit) -> Result<bool, Error>;
+    /// Disables the unit file, returning true if it was already disabled.
+    fn disable(&self, unit: &SystemdUnit) -> Result<bool, Error>;
+    /// Starts the unit.
+    fn start(&self, unit: &SystemdUnit) -> Result<(), Error>;
+    /// Stops the unit.
+    fn stop(&self, unit: &SystemdUnit) -> Result<(), Error>;
+    /// Restarts the unit, starting it if it is not running.
+    fn restart(&self, unit: &SystemdUnit) -> Result<(), Error>;
+    /// Asks the running unit to reload its configuration, without restarting it.
+    fn reload(&self, unit: &SystemdUnit) -> Result<(), Error>;
+    /// Masks the unit file, so that it cannot be started, returning true if it was already masked.
+    fn mask(&self, unit: &SystemdUnit) -> Result<bool, Error>;
+    /// Reads the contents of the unit's file.
+    fn unit_file(&self, unit: &SystemdUnit) -> Result<String, Error>;
+    /// Replaces the contents of the unit's file.
+    fn save_unit_file(&self, unit: &SystemdUnit, contents: &str) -> Result<(), Error>;
+    /// Lists the units that the unit depends upon.
+    fn dependencies(&self, unit: &SystemdUnit) -> Result<String, Error>;
+    /// Reads the journal entries that are matched by the query.
+    fn journal(&self, query: &JournalQuery) -> Result<String, Error>;
+    /// Restricts the query to the journal entries that were logged by this manager, such as those of a container or
+    /// of a user, which is used when the entries are read or exported locally.
+    fn scope_journal(&self, query: &JournalQuery) -> Result<JournalQuery, Error> { Ok(query.clone()) }
+    /// The query that matches the journal entries of the unit as they were logged by this manager.
+    fn journal_query(&self, unit: &SystemdUnit) -> Result<JournalQuery, Error> {
+        self.scope_journal(&unit.journal_query())
+    }
+    /// Lists the time that each unit took to initialize during boot.
+    fn blame(&self) -> Result<Vec<Analyze>, Error>;
+    /// Obtains the time that the kernel, the userspace, and the whole boot took.
+    fn boot_time(&self) -> Result<(String, String, String), Error>;
+
+    /// Watches the manager for changes to the state of its units, sending the bus type and name of each unit that
+    /// changed, until `stop` is set or the connection is lost. Managers that cannot be watched return at once.
+    fn watch(&self, _: Sender<(BusType, String)>, _: &AtomicBool) -> Result<(), Error> { Ok(()) }
+
+    /// Collects the state of the unit once the job that was queued for it has finished, as starting or stopping a
+    /// unit returns as soon as the job is queued. The unit is reloaded regardless after `JOB_TIMEOUT`.
+    fn reload_settled(&self, unit: &SystemdUnit) -> Result<SystemdUnit, Error> {
+        let deadline = Instant::now() + JOB_TIMEOUT;
+        while Instant::now() < deadline {
+            match self.unit_properties(unit, "org.freedesktop.systemd1.Unit") {
+                Ok(ref properties) if has_pending_job(properties) => thread::sleep(JOB_POLL_INTERVAL),
+                _ => break,
+            }
+        }
+        self.reload_unit(unit)
+    }
+}
+
+/// How long to wait for a queued job to finish, which matches systemd's default start timeout.
+pub const JOB_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// How often the unit is checked for a pending job while waiting for it to finish.
+const JOB_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often the watchers of both buses check whether they are to stop.
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Whether the `Job` property of a unit refers to a queued or running job, as its id is zero when no job is pending.
+fn has_pending_job(properties: &BTreeMap<String, MessageItem>) -> bool {
+    match properties.get("Job") {
+        Some(&MessageItem::Struct(ref fields)) => match fields.first() {
+            Some(&MessageItem::UInt32(id)) => id != 0,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// The manager of the local machine, which is reached through the system or session bus. Unit files, the journal,
+/// and boot times are read through the local filesystem and commands.
+#[derive(Clone, Debug)]
+pub struct LocalBackend {
+    bustype: BusType,
+}
+
+impl LocalBackend {
+    /// Manages the system's units with `BusType::System`, or the user's units with `BusType::Session`.
+    pub fn new(bustype: BusType) -> LocalBackend { LocalBackend { bustype: bustype } }
+
+    /// The bus that the manager is reached through.
+    pub fn bustype(&self) -> BusType { self.bustype }
+}
+
+impl SystemdBackend for LocalBackend {
+    fn name(&self) -> String {
+        String::from(if self.bustype == BusType::Session { "the session bus" } else { "the system bus" })
+    }
+
+    fn list_unit_files(&self) -> Result<Vec<SystemdUnit>, Error> { dbus::list_unit_files(self.bustype) }
+
+    fn reload_unit(&self, unit: &SystemdUnit) -> Result<SystemdUnit, Error> {
+        dbus::reload_unit_on(&Bus::from(self.bustype), unit)
+    }
+
+    fn unit_properties(&self, unit: &SystemdUnit, interface: &str) -> Result<BTreeMap<String, MessageItem>, Error> {
+        dbus::unit_properties_on(&Bus::from(self.bustype), &unit.name, interface)
+    }
+
+    fn enable(&self, unit: &SystemdUnit) -> Result<bool, Error> {
+        dbus::enable_on(&Bus::from(self.bustype), &unit.name)
+    }
+
+    fn disable(&self, unit: &SystemdUnit) -> Result<bool, Error> {
+        dbus::disable_on(&Bus::from(self.bustype), &unit.name)
+    }
+
+    fn start(&self, unit: &SystemdUnit) -> Result<(), Error> { dbus::start_on(&Bus::from(self.bustype), &unit.name) }
+
+    fn stop(&self, unit: &SystemdUnit) -> Result<(), Error> { dbus::stop_on(&Bus::from(self.bustype), &unit.name) }
+
+    fn restart(&self, unit: &SystemdUnit) -> Result<(), Error> {
+        dbus::restart_on(&Bus::from(self.bustype), &unit.name)
+    }
+
+    fn reload(&self, unit: &SystemdUnit) -> Result<(), Error> { dbus::reload_on(&Bus::from(self.bustype), &unit.name) }
+
+    fn mask(&self, unit: &SystemdUnit) -> Result<bool, Error> { dbus::mask_on(&Bus::from(self.bustype), &unit.name) }
+
+    fn unit_file(&self, unit: &SystemdUnit) -> Result<String, Error> { unit.get_info() }
+
+    fn save_unit_file(&self, unit: &SystemdUnit, contents: &str) -> Result<(), Error> { unit.save_info(contents) }
+
+    fn dependencies(&self, unit: &SystemdUnit) -> Result<String, Error> { unit.list_dependencies() }
+
+    fn journal(&self, query: &JournalQuery) -> Result<String, Error> { query.read() }
+
+    fn blame(&self) -> Result<Vec<Analyze>, Error> { Analyze::blame() }
+
+    fn boot_time(&self) -> Result<(String, String, String), Error> { Analyze::time() }
+
+    fn watch(&self, changed: Sender<(BusType, String)>, stop: &AtomicBool) -> Result<(), Error> {
+        dbus::watch_on(&Bus::from(self.bustype), self.bustype, &changed, stop)
+    }
+}
+
+/// The system's manager and the user's manager of the local machine together, so that the units of both buses may be
+/// listed at once. Every operation upon a unit is performed by the manager of the unit's own bus.
+#[derive(Clone, Debug)]
+pub struct CombinedBackend {
+    system: LocalBackend,
+    session: LocalBackend,
+}
+
+impl CombinedBackend {
+    /// Manages the units of the system bus and of the session bus.
+    pub fn new() -> CombinedBackend {
+        CombinedBackend { system: LocalBackend::new(BusType::System), session: LocalBackend::new(BusType::Session) }
+    }
+
+    /// Obtains the manager that the unit belongs to.
+    fn manager(&self, unit: &SystemdUnit) -> &LocalBackend {
+        if unit.bustype == BusType::Session { &self.session } else { &self.system }
+    }
+}
+
+impl Default for CombinedBackend {
+    fn default() -> CombinedBackend { CombinedBackend::new() }
+}
+
+impl SystemdBackend for CombinedBackend {
+    fn name(&self) -> String { String::from("the system and session buses") }
+
+    fn list_unit_files(&self) -> Result<Vec<SystemdUnit>, Error> {
+        self.list_available_unit_files().map(|(units, _)| units)
+    }
+
+    /// The units of both buses are sorted by their names, with the system's unit first if both have the same name.
+    /// Only the system's units are listed when the session bus is unavailable, such as when running as root.
+    fn list_available_unit_files(&self) -> Result<(Vec<SystemdUnit>, Option<Error>), Error> {
+        let mut units = self.system.list_unit_files()?;
+        let session_error = match self.session.list_unit_files() {
+            Ok(session_units) => {
+                units.extend(session_units);
+                None
+            }
+            Err(why) => Some(why),
+        };
+        units.sort_by_key(|unit| (unit.name.to_lowercase(), unit.bustype == BusType::Session));
+        Ok((units, session_error))
+    }
+
+    fn reload_unit(&self, unit: &SystemdUnit) -> Result<SystemdUnit, Error> { self.manager(unit).reload_unit(unit) }
+
+    fn unit_properties(&self, unit: &SystemdUnit, interface: &str) -> Result<BTreeMap<String, MessageItem>, Error> {
+        self.manager(unit).unit_properties(unit, interface)
+    }
+
+    fn enable(&self, unit: &SystemdUnit) -> Result<bool, Error> { self.manager(unit).enable(unit) }
+
+    fn disable(&self, unit: &SystemdUnit) -> Result<bool, Error> { self.manager(unit).disable(unit) }
+
+    fn start(&self, unit: &SystemdUnit) -> Result<(), Error> { self.manager(unit).start(unit) }
+
+    fn stop(&self, unit: &SystemdUnit) -> Result<(), Error> { self.manager(unit).stop(unit) }
+
+    fn restart(&self, unit: &SystemdUnit) -> Result<(), Error> { self.manager(unit).restart(unit) }
+
+    fn reload(&self, unit: &SystemdUnit) -> Result<(), Error> { self.manager(unit).reload(unit) }
+
+    fn mask(&self, unit: &SystemdUnit) -> Result<bool, Error> { self.manager(unit).mask(unit) }
+
+    fn unit_file(&self, unit: &SystemdUnit) -> Result<String, Error> { self.manager(unit).unit_file(unit) }
+
+    fn save_unit_file(&self, unit: &SystemdUnit, contents: &str) -> Result<(), Error> {
+        self.manager(unit).save_unit_file(unit, contents)
+    }
+
+    fn dependencies(&self, unit: &SystemdUnit) -> Result<String, Error> { self.manager(unit).dependencies(unit) }
+
+    /// The query names the bus whose journal is read.
+    fn journal(&self, query: &JournalQuery) -> Result<String, Error> { query.read() }
+
+    fn blame(&self) -> Result<Vec<Analyze>, Error> { self.system.blame() }
+
+    fn boot_time(&self) -> Result<(String, String, String), Error> { self.system.boot_time() }
+
+    /// The session bus is watched on a thread of its own. Both buses are watched until `stop` is set, or until the
+    /// watcher of either bus fails, so that the failure is reported rather than waiting upon the other watcher.
+    fn watch(&self, changed: Sender<(BusType, String)>, stop: &AtomicBool) -> Result<(), Error> {
+        let session_changed = changed.clone();
+        let stopped = AtomicBool::new(false);
+        thread::scope(|scope| {
+            let stopped = &stopped;
+            let session = scope.spawn(move || {
+                let result = self.session.watch(session_changed, stopped);
+                stopped.store(true, Ordering::SeqCst);
+                result
+            });
+            // Relays `stop` to both watchers, until either of them has ended.
+            scope.spawn(move || {
+                while !stopped.load(Ordering::SeqCst) {
+                    if stop.load(Ordering::SeqCst) {
+                        stopped.store(true, Ordering::SeqCst);
+                    } else {
+                        thread::sleep(STOP_POLL_INTERVAL);
+                    }
+                }
+            });
+            let system = self.system.watch(changed, stopped);
+            stopped.store(true, Ordering::SeqCst);
+            system.and(session.join().unwrap_or(Ok(())))
+        })
+    }
+}
+
+/// A manager that is reached through the bus at an arbitrary address, such as `unix:path=/run/systemd/private` or
+/// the bus of a container. Everything that is not available over D-Bus, such as the contents of unit files and the
+/// journal, is reported as unavailable.
+#[derive(Clone, Debug)]
+pub struct AddressBackend {
+    address: String,
+    bustype: BusType,
+    name: String,
+    /// Whether the address is that of a message bus, which must be registered with, rather than that of the
+    /// manager's own socket.
+    register: bool,
+}
+
+impl AddressBackend {
+    /// Manages the units of the manager at the address, which is the system's manager if the bus type is
+    /// `BusType::System`, or a user's manager if the bus type is `BusType::Session`. The address is considered to be
+    /// that of a message bus, unless it is the manager's own socket, such as `unix:path=/run/systemd/private`.
+    pub fn new(address: &str, bustype: BusType) -> AddressBackend {
+        AddressBackend {
+            address: address.to_owned(),
+            bustype: bustype,
+            name: format!("the bus at {}", address),
+            register: !address.contains("systemd/private"),
+        }
+    }
+
+    /// Manages the units of a remote host's manager, which is reached by running `systemd-stdio-bridge` on the host
+    /// through `ssh`, as `systemctl --host` does. The host may be given as `user@host`.
+    pub fn ssh(host: &str, bustype: BusType) -> AddressBackend {
+        // The host follows `--`, so that a host which begins with `-` is not taken to be an option of `ssh`.
+        let mut address = format!("unixexec:path=ssh,argv1=-xT,argv2=--,argv3={},argv4=systemd-stdio-bridge",
+            escape_address_value(host));
+        if bustype == BusType::Session {
+            address.push_str(",argv5=--user");
+        }
+        // The bridge connects to the host's bus, which the connection registers with through it.
+        AddressBackend { address: address, bustype: bustype, name: host.to_owned(), register: true }
+    }
+
+    /// The address of the bus that the manager is reached through.
+    pub fn address(&self) -> &str { &self.address }
+
+    fn bus(&self) -> Bus { Bus::Address(self.address.clone(), self.register) }
+
+    /// The error that is returned for operations that cannot be performed over D-Bus.
+    fn unavailable(&self, what: &str) -> Error {
+        Error::JobFailed(format!("{} is not available through {}", what, self.address))
+    }
+}
+
+impl SystemdBackend for AddressBackend {
+    fn name(&self) -> String { self.name.clone() }
+
+    fn list_unit_files(&self) -> Result<Vec<SystemdUnit>, Error> {
+        dbus::list_unit_files_on(&self.bus(), self.bustype)
+    }
+
+    fn reload_unit(&self, unit: &SystemdUnit) -> Result<SystemdUnit, Error> { dbus::reload_unit_on(&self.bus(), unit) }
+
+    fn unit_properties(&self, unit: &SystemdUnit, interface: &str) -> Result<BTreeMap<String, MessageItem>, Error> {
+        dbus::unit_properties_on(&self.bus(), &unit.name, interface)
+    }
+
+    fn enable(&self, unit: &SystemdUnit) -> Result<bool, Error> { dbus::enable_on(&self.bus(), &unit.name) }
+
+    fn disable(&self, unit: &SystemdUnit) -> Result<bool, Error> { dbus::disable_on(&self.bus(), &unit.name) }
+
+    fn start(&self, unit: &SystemdUnit) -> Result<(), Error> { dbus::start_on(&self.bus(), &unit.name) }
+
+    fn stop(&self, unit: &SystemdUnit) -> Result<(), Error> { dbus::stop_on(&self.bus(), &unit.name) }
+
+    fn restart(&self, unit: &SystemdUnit) -> Result<(), Error> { dbus::restart_on(&self.bus(), &unit.name) }
+
+    fn reload(&self, unit: &SystemdUnit) -> Result<(), Error> { dbus::reload_on(&self.bus(), &unit.name) }
+
+    fn mask(&self, unit: &SystemdUnit) -> Result<bool, Error> { dbus::mask_on(&self.bus(), &unit.name) }
+
+    fn unit_file(&self, unit: &SystemdUnit) -> Result<String, Error> {
+        Err(self.unavailable(&format!("the unit file of {}", unit.name)))
+    }
+
+    fn save_unit_file(&self, unit: &SystemdUnit, _: &str) -> Result<(), Error> {
+        Err(self.unavailable(&format!("the unit file of {}", unit.name)))
+    }
+
+    /// The dependencies are collected from the properties of the unit, rather than through `systemctl`.
+    fn dependencies(&self, unit: &SystemdUnit) -> Result<String, Error> {
+        self.unit_properties(unit, "org.freedesktop.systemd1.Unit").map(|properties| list_dependencies(&properties))
+    }
+
+    fn journal(&self, _: &JournalQuery) -> Result<String, Error> { Err(self.unavailable("the journal")) }
+
+    fn scope_journal(&self, _: &JournalQuery) -> Result<JournalQuery, Error> { Err(self.unavailable("the journal")) }
+
+    fn blame(&self) -> Result<Vec<Analyze>, Error> { Err(self.unavailable("systemd-analyze")) }
+
+    fn boot_time(&self) -> Result<(String, String, String), Error> { Err(self.unavailable("systemd-analyze")) }
+
+    fn watch(&self, changed: Sender<(BusType, String)>, stop: &AtomicBool) -> Result<(), Error> {
+        dbus::watch_on(&self.bus(), self.bustype, &changed, stop)
+    }
+}
+
+/// An in-memory manager, whose units are started, stopped, enabled, and disabled without affecting the system. It
+/// allows the front-ends to be exercised on machines without systemd.
+#[derive(Debug, Default)]
+pub struct FakeBackend {
+    units: Mutex<BTreeMap<String, SystemdUnit>>,
+}
+
+impl FakeBackend {
+    /// Manages the given units.
+    pub fn new(units: Vec<SystemdUnit>) -> FakeBackend {
+        FakeBackend { units: Mutex::new(units.into_iter().map(|unit| (unit.name.clone(), unit)).collect()) }
+    }
+
+    /// Obtains the current state of the unit with the given name.
+    pub fn unit(&self, name: &str) -> Option<SystemdUnit> {
+        self.units.lock().unwrap().get(name).cloned()
+    }
+
+    /// Modifies the stored unit with the given name, returning the result of the modification.
+    fn modify<T, F: FnOnce(&mut SystemdUnit) -> T>(&self, name: &str, modify: F) -> Result<T, Error> {
+        self.units.lock().unwrap().get_mut(name).map(modify).ok_or_else(|| Error::NoSuchUnit(name.to_owned()))
+    }
+}
+
+impl SystemdBackend for FakeBackend {
+    fn name(&self) -> String { String::from("the fake bus") }
+
+    fn list_unit_files(&self) -> Result<Vec<SystemdUnit>, Error> {
+        Ok(self.units.lock().unwrap().values().cloned().collect())
+    }
+
+    fn reload_unit(&self, unit: &SystemdUnit) -> Result<SystemdUnit, Error> {
+        self.modify(&unit.name, |unit| unit.clone())
+    }
+
+    fn unit_properties(&self, unit: &SystemdUnit, _: &str) -> Result<BTreeMap<String, MessageItem>, Error> {
+        let unit = self.reload_unit(unit)?;
+        let mut properties = BTreeMap::new();
+        properties.insert(String::from("Id"), MessageItem::Str(unit.name.clone()));
+        properties.insert(String::from("Description"), MessageItem::Str(unit.description.clone()));
+        properties.insert(String::from("ActiveState"), MessageItem::Str(unit.active_state.as_str().to_owned()));
+        properties.insert(String::from("SubState"), MessageItem::Str(unit.sub_state.clone()));
+        properties.insert(String::from("UnitFileState"), MessageItem::Str(unit.state.as_str().to_owned()));
+        properties.insert(String::from("FragmentPath"), MessageItem::Str(unit.path.clone()));
+        Ok(properties)
+    }
+
+    fn enable(&self, unit: &SystemdUnit) -> Result<bool, Error> {
+        self.modify(&unit.name, |unit| {
+            let already = unit.state == UnitState::Enabled;
+            unit.state = UnitState::Enabled;
+            already
+        })
+    }
+
+    fn disable(&self, unit: &SystemdUnit) -> Result<bool, Error> {
+        self.modify(&unit.name, |unit| {
+            let already = unit.state != UnitState::Enabled;
+            if !already {
+                unit.state = UnitState::Disabled;
+            }
+            already
+        })
+    }
+
+    fn start(&self, unit: &SystemdUnit) -> Result<(), Error> {
+        self.modify(&unit.name, |unit| {
+            unit.active_state = ActiveState::Active;
+            unit.sub_state = String::from("running");
+        })
+    }
+
+    fn stop(&self, unit: &SystemdUnit) -> Result<(), Error> {
+        self.modify(&unit.name, |unit| {
+            unit.active_state = ActiveState::Inactive;
+            unit.sub_state = String::from("dead");
+        })
+    }
+
+    fn restart(&self, unit: &SystemdUnit) -> Result<(), Error> { self.start(unit) }
+
+    /// Reloading leaves the state of the unit unchanged.
+    fn reload(&self, unit: &SystemdUnit) -> Result<(), Error> { self.modify(&unit.name, |_| ()) }
+
+    fn mask(&self, unit: &SystemdUnit) -> Result<bool, Error> {
+        self.modify(&unit.name, |unit| {
+            let already = unit.state == UnitState::Masked;
+            unit.state = UnitState::Masked;
+            already
+        })
+    }
+
+    fn unit_file(&self, unit: &SystemdUnit) -> Result<String, Error> {
+        self.reload_unit(unit).map(|unit| format!("[Unit]\nDescription={}\n", unit.description))
+    }
+
+    /// The contents are discarded, as the unit files are generated from the units.
+    fn save_unit_file(&self, unit: &SystemdUnit, _: &str) -> Result<(), Error> { self.modify(&unit.name, |_| ()) }
+
+    fn dependencies(&self, unit: &SystemdUnit) -> Result<String, Error> {
+        self.reload_unit(unit).map(|_| String::new())
+    }
+
+    fn journal(&self, _: &JournalQuery) -> Result<String, Error> { Ok(String::new()) }
+
+    fn blame(&self) -> Result<Vec<Analyze>, Error> { Ok(Vec::new()) }
+
+    fn boot_time(&self) -> Result<(String, String, String), Error> {
+        Ok((String::from("0ms"), String::from("0ms"), String::from("0ms")))
+    }
+}
+
+/// Escapes a value of a D-Bus address, in which only alphanumeric characters and `-_/.\*` may appear unescaped.
+pub(crate) fn escape_address_value(value: &str) -> String {
+    value.bytes()
+        .map(|byte| match byte {
+            b'-' | b'_' | b'/' | b'.' | b'\\' | b'*' => (byte as char).to_string(),
+            _ if byte.is_ascii_alphanumeric() => (byte as char).to_string(),
+            _ => format!("%{:02x}", byte),
+        })
+        .collect()
+}
+
+/// Lists the units that are named by the dependency properties of a unit, beneath the name of each property.
+fn list_dependencies(properties: &BTreeMap<String, MessageItem>) -> String {
+    let mut output = String::new();
+    for property in &["Requires", "Requisite", "Wants", "BindsTo", "PartOf"] {
+        if let Some(&MessageItem::Array(ref units, _)) = properties.get(*property) {
+            let units: Vec<&str> = units.iter()
+                .filter_map(|unit| match *unit { MessageItem::Str(ref unit) => Some(unit.as_str()), _ => None })
+                .collect();
+            if !units.is_empty() {
+                output.push_str(&format!("{}:\n", property));
+                for unit in units {
+                    output.push_str(&format!("  {}\n", unit));
+                }
+            }
+        }
+    }
+    output
+}
+
+#[test]
+fn test_list_dependencies() {
+    let mut properties = BTreeMap::new();
+    let basic = MessageItem::Str(String::from("basic.target"));
+    properties.insert(String::from("Requires"), MessageItem::Array(vec![basic], "s".into()));
+    properties.insert(String::from("Wants"), MessageItem::Array(vec![
+        MessageItem::Str(String::from("network.target")),
+        MessageItem::Str(String::from("sshdgenkeys.service")),
+    ], "s".into()));
+    properties.insert(String::from("BindsTo"), MessageItem::Array(Vec::new(), "s".into()));
+    assert_eq!(list_dependencies(&properties),
+        "Requires:\n  basic.target\nWants:\n  network.target\n  sshdgenkeys.service\n");
+}
+
+#[test]
+fn test_has_pending_job() {
+    let job = |id| {
+        let mut properties = BTreeMap::new();
+        properties.insert(String::from("Job"), MessageItem::Struct(vec![
+            MessageItem::UInt32(id),
+            MessageItem::ObjectPath(format!("/org/freedesktop/systemd1/job/{}", id).into()),
+        ]));
+        properties
+    };
+    assert!(has_pending_job(&job(4213)));
+    assert!(!has_pending_job(&job(0)));
+    assert!(!has_pending_job(&BTreeMap::new()));
+}
+
+#[test]
+fn test_ssh_address() {
+    assert_eq!(AddressBackend::ssh("root@web-1.example.com", BusType::System).address(),
+        "unixexec:path=ssh,argv1=-xT,argv2=--,argv3=root%40web-1.example.com,argv4=systemd-stdio-bridge");
+    assert_eq!(AddressBackend::ssh("web-1", BusType::Session).address(),
+        "unixexec:path=ssh,argv1=-xT,argv2=--,argv3=web-1,argv4=systemd-stdio-bridge,argv5=--user");
+    assert_eq!(AddressBackend::ssh("-oProxyCommand=evil", BusType::System).address(),
+        "unixexec:path=ssh,argv1=-xT,argv2=--,argv3=-oProxyCommand%3devil,argv4=systemd-stdio-bridge");
+    assert_eq!(AddressBackend::ssh("web-1", BusType::Session).name(), "web-1");
+}
+
+#[test]
+fn test_address_registration() {
+    let bus = |address: &str| AddressBackend::new(address, BusType::System).bus();
+    assert_eq!(bus("unix:path=/run/systemd/private"),
+        Bus::Address(String::from("unix:path=/run/systemd/private"), false));
+    assert_eq!(bus("unix:path=/run/user/1000/systemd/private"),
+        Bus::Address(String::from("unix:path=/run/user/1000/systemd/private"), false));
+    assert_eq!(bus("unix:path=/run/dbus/system_bus_socket"),
+        Bus::Address(String::from("unix:path=/run/dbus/system_bus_socket"), true));
+    assert_eq!(AddressBackend::ssh("web-1", BusType::System).bus(), Bus::Address(String::from(
+        "unixexec:path=ssh,argv1=-xT,argv2=--,argv3=web-1,argv4=systemd-stdio-bridge"), true));
+}
+
+#[test]
+fn test_fake_backend() {
+    let sshd = SystemdUnit::loaded("sshd.service", "OpenSSH Daemon", UnitState::Disabled, ActiveState::Inactive);
+    let backend = FakeBackend::new(vec![sshd.clone()]);
+
+    assert_eq!(backend.enable(&sshd), Ok(false));
+    assert_eq!(backend.enable(&sshd), Ok(true));
+    assert_eq!(backend.start(&sshd), Ok(()));
+    let reloaded = backend.reload_unit(&sshd).unwrap();
+    assert_eq!(reloaded.state, UnitState::Enabled);
+    assert_eq!(reloaded.active_state, ActiveState::Active);
+    assert_eq!(backend.disable(&sshd), Ok(false));
+    assert_eq!(backend.unit("sshd.service").map(|unit| unit.state), Some(UnitState::Disabled));
+    assert_eq!(backend.stop(&sshd), Ok(()));
+    assert_eq!(backend.restart(&sshd), Ok(()));
+    assert_eq!(backend.reload(&sshd), Ok(()));
+    assert_eq!(backend.mask(&sshd), Ok(false));
+    assert_eq!(backend.mask(&sshd), Ok(true));
+    assert_eq!(backend.save_unit_file(&sshd, "[Unit]\nDescription=OpenSSH Daemon\n"), Ok(()));
+    let reloaded = backend.reload_unit(&sshd).unwrap();
+    assert_eq!((reloaded.state, reloaded.active_state), (UnitState::Masked, ActiveState::Active));
+
+    let mut missing = sshd.clone();
+    missing.name = String::from("cups.service");
+    assert_eq!(backend.stop(&missing), Err(Error::NoSuchUnit(String::from("cups.service"))));
+}
@@ -0,0 +1,128 @@
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use super::Error;
+use super::dbus::dbus::{self, BusType, Connection};
+
+/// A bus that the systemd manager is reached through: the system or session bus, or the bus at an address.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Bus {
+    /// The system or session bus.
+    Type(BusType),
+    /// The bus at the address, which is registered with when it is a message bus, rather than a peer-to-peer
+    /// connection to the manager such as `unix:path=/run/systemd/private`.
+    Address(String, bool),
+}
+
+impl Bus {
+    /// Opens a new connection to the bus.
+    pub fn open(&self) -> Result<Connection, dbus::Error> {
+        match *self {
+            Bus::Type(bustype) => Connection::get_private(bustype),
+            Bus::Address(ref address, register) => {
+                let connection = Connection::open_private(address)?;
+                // A message bus only routes the messages of connections that have said `Hello` to it.
+                if register {
+                    connection.register()?;
+                }
+                Ok(connection)
+            }
+        }
+    }
+}
+
+impl From<BusType> for Bus {
+    fn from(bustype: BusType) -> Bus { Bus::Type(bustype) }
+}
+
+/// A call that is performed on the thread of its bus. It is given the connection to the bus, or the reason that no
+/// connection could be opened, and returns true if the connection was lost during the call.
+type Task = Box<dyn FnOnce(Result<&Connection, Error>) -> bool + Send>;
+
+/// Connections cannot be shared between threads, so a single connection to each bus is kept open by a thread of its
+/// own, which performs the calls that are sent to it in turn. Each bus has a thread of its own so that a bus which
+/// stops replying, such as that of an unreachable host, does not hold up the calls to the other buses.
+fn bus_thread(bus: &Bus) -> Result<Sender<Task>, Error> {
+    static SENDERS: OnceLock<Mutex<Vec<(Bus, Sender<Task>)>>> = OnceLock::new();
+    let mut senders = SENDERS.get_or_init(|| Mutex::new(Vec::new())).lock()
+        .map_err(|_| Error::BusUnavailable(String::from("the bus threads have stopped")))?;
+    if let Some(&(_, ref sender)) = senders.iter().find(|&&(ref open, _)| open == bus) {
+        return Ok(sender.clone())
+    }
+
+    let (sender, receiver) = mpsc::channel::<Task>();
+    let thread_bus = bus.clone();
+    thread::spawn(move || {
+        let mut connection: Option<Connection> = None;
+        for task in receiver {
+            // Open a connection to the bus if one is not already open.
+            if connection.is_none() {
+                match thread_bus.open() {
+                    Ok(opened) => connection = Some(opened),
+                    Err(why) => {
+                        task(Err(Error::BusUnavailable(why.to_string())));
+                        continue
+                    }
+                }
+            }
+
+            if task(Ok(connection.as_ref().unwrap())) {
+                // The connection is closed so that a new connection is opened for the next call.
+                connection = None;
+            }
+        }
+    });
+    senders.push((bus.clone(), sender.clone()));
+    Ok(sender)
+}
+
+/// The outcome of a call that was sent to the thread of its bus.
+enum Outcome<T> {
+    /// The call was performed, and returned its result.
+    Performed(Result<T, Error>),
+    /// No connection could be opened to the bus, so the call was not performed.
+    Unopened(Error),
+}
+
+/// Performs the call with the shared connection to the given bus, and returns its result. If no connection could
+/// be opened to the bus, opening one is tried once more. A call that was performed is never repeated, as calls such
+/// as `StartUnit` would then be performed twice, but the connection is reopened for the next call if it was lost.
+pub fn call<T, F>(bus: &Bus, call: F) -> Result<T, Error>
+    where T: Send + 'static,
+          F: Fn(&Connection) -> Result<T, Error> + Send + Sync + 'static
+{
+    let call = Arc::new(call);
+    let outcome = match request(bus, call.clone())? {
+        Outcome::Unopened(_) => request(bus, call)?,
+        outcome => outcome,
+    };
+    match outcome {
+        Outcome::Performed(result) => result,
+        Outcome::Unopened(why) => Err(why),
+    }
+}
+
+/// Sends the call to the thread of its bus and waits for its outcome.
+fn request<T, F>(bus: &Bus, call: Arc<F>) -> Result<Outcome<T>, Error>
+    where T: Send + 'static,
+          F: Fn(&Connection) -> Result<T, Error> + Send + Sync + 'static
+{
+    let (sender, receiver) = mpsc::channel();
+    let task: Task = Box::new(move |connection| {
+        let (outcome, lost) = match connection {
+            Ok(connection) => {
+                let result = call(connection);
+                let lost = match result { Err(Error::BusUnavailable(_)) => true, _ => false };
+                (Outcome::Performed(result), lost)
+            }
+            Err(why) => (Outcome::Unopened(why), false),
+        };
+        // The caller is waiting for the outcome, so the error can only occur if the caller has panicked.
+        let _ = sender.send(outcome);
+        lost
+    });
+
+    let unavailable = || Error::BusUnavailable(String::from("the bus thread has stopped"));
+    bus_thread(bus)?.send(task).map_err(|_| unavailable())?;
+    receiver.recv().map_err(|_| unavailable())
+}
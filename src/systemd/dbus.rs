@@ -1,8 +1,16 @@
+//! Manages units through the D-Bus API of the systemd manager, `org.freedesktop.systemd1.Manager`.
+
 pub extern crate dbus;
 extern crate quickersort;
-use super::dbus::dbus::MessageItem;
-use super::{SystemdUnit, UnitType, UnitState};
+use super::dbus::dbus::{ConnectionItem, MessageItem};
+use super::connection::{self, Bus};
+use super::Error;
+use super::{get_unit_description, ActiveState, SystemdUnit, UnitType, UnitState};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
 
 /// Takes a systemd dbus function as input and returns the result as a `dbus::Message`.
 macro_rules! dbus_message {
@@ -15,79 +23,341 @@ macro_rules! dbus_message {
     }}
 }
 
-/// Takes a `dbus::Message` as input and makes a connection to dbus, returning the reply.
-macro_rules! dbus_connect {
-    ($message:expr, $bus_type:expr) => {
-        dbus::Connection::get_private($bus_type).unwrap().
-            send_with_reply_and_block($message, 4000)
-    }
+/// Calls a method of the systemd manager with the given arguments through the shared connection to the bus,
+/// returning the items of the reply.
+fn call_manager(bus: &Bus, method: &'static str, arguments: Vec<MessageItem>) -> Result<Vec<MessageItem>, Error> {
+    connection::call(bus, move |connection| {
+        let mut message = dbus_message!(method);
+        message.append_items(&arguments);
+        connection.send_with_reply_and_block(message, 4000)
+            .map(|reply| reply.get_items())
+            .map_err(Error::from)
+    })
 }
 
+/// The actions that the systemd manager performs upon a unit.
 pub trait Dbus {
-    fn is_enabled(&self) -> bool;
-    fn enable(&self) -> Result<bool, String>;
-    fn disable(&self) -> Result<bool, String>;
-    fn start(&self) -> Option<String>;
-    fn stop(&self) -> Option<String>;
+    /// Returns true if the unit file is enabled.
+    fn is_enabled(&self) -> Result<bool, Error>;
+    /// Enables the unit file, returning true if it was already enabled.
+    fn enable(&self) -> Result<bool, Error>;
+    /// Disables the unit file, returning true if it was already disabled.
+    fn disable(&self) -> Result<bool, Error>;
+    /// Starts the unit.
+    fn start(&self) -> Result<(), Error>;
+    /// Stops the unit.
+    fn stop(&self) -> Result<(), Error>;
 }
 
 
 impl Dbus for SystemdUnit {
-    /// Returns the current enablement status of the unit.
-    fn is_enabled(&self) -> bool {
-        list_unit_files(self.bustype).iter()
-            // Find the specific unit that we waant to obtain the status from
-            .find(|unit| &unit.path == &self.path)
-            // Map the contained value of that unit and return true if the `UnitState` is `Enabled`.
-            .map_or(false, |unit| unit.state == UnitState::Enabled)
+    fn is_enabled(&self) -> Result<bool, Error> { is_enabled_on(&Bus::from(self.bustype), &self.name) }
+
+    fn enable(&self) -> Result<bool, Error> { enable_on(&Bus::from(self.bustype), &self.name) }
+
+    fn disable(&self) -> Result<bool, Error> { disable_on(&Bus::from(self.bustype), &self.name) }
+
+    fn start(&self) -> Result<(), Error> { start_on(&Bus::from(self.bustype), &self.name) }
+
+    fn stop(&self) -> Result<(), Error> { stop_on(&Bus::from(self.bustype), &self.name) }
+}
+
+/// Returns the current enablement status of the unit file with the given name.
+pub(crate) fn is_enabled_on(bus: &Bus, name: &str) -> Result<bool, Error> {
+    let items = call_manager(bus, "GetUnitFileState", vec![name.into()])?;
+    // Return true if the reply states that the unit file is `enabled`.
+    match items.get(0) {
+        Some(&MessageItem::Str(ref state)) => Ok(UnitState::from_name(state) == UnitState::Enabled),
+        _ => Err(Error::Parse(format!("the unit file state of {} is missing from the reply", name))),
     }
+}
+
+/// Takes the name of a unit file and enables it via dbus.
+/// If dbus replies with `[Bool(true), Array([], "(sss)")]`, the unit file is already enabled.
+pub(crate) fn enable_on(bus: &Bus, name: &str) -> Result<bool, Error> {
+    let arguments = vec![[name][..].into(), false.into(), true.into()];
+    call_manager(bus, "EnableUnitFiles", arguments)
+        // Return `Ok(true)` if the unit is already enabled
+        .map(|items| is_enabled(&items))
+}
+
+/// Takes the name of a unit file and disables it via dbus.
+/// If dbus replies with `[Array([], "(sss)")]`, the unit file is already disabled.
+pub(crate) fn disable_on(bus: &Bus, name: &str) -> Result<bool, Error> {
+    let arguments = vec![[name][..].into(), false.into()];
+    call_manager(bus, "DisableUnitFiles", arguments)
+        // Return `Ok(true)` if the unit is already disabled
+        .map(|items| is_disabled(&items))
+}
+
+/// Takes a unit name as input and attempts to start it. It returns an error if an error occurs.
+pub(crate) fn start_on(bus: &Bus, name: &str) -> Result<(), Error> {
+    call_manager(bus, "StartUnit", vec![name.into(), "fail".into()]).map(|_| ())
+}
+
+/// Takes a unit name as input and attempts to stop it.
+pub(crate) fn stop_on(bus: &Bus, name: &str) -> Result<(), Error> {
+    call_manager(bus, "StopUnit", vec![name.into(), "fail".into()]).map(|_| ())
+}
+
+/// Takes a unit name as input and attempts to restart it, starting it if it is not running.
+pub(crate) fn restart_on(bus: &Bus, name: &str) -> Result<(), Error> {
+    call_manager(bus, "RestartUnit", vec![name.into(), "fail".into()]).map(|_| ())
+}
+
+/// Takes a unit name as input and asks the unit to reload its configuration, without restarting it.
+pub(crate) fn reload_on(bus: &Bus, name: &str) -> Result<(), Error> {
+    call_manager(bus, "ReloadUnit", vec![name.into(), "fail".into()]).map(|_| ())
+}
+
+/// Takes the name of a unit file and masks it via dbus, so that it cannot be started. The reply has the same form
+/// as that of `DisableUnitFiles`, which lists no changes if the unit file is already masked.
+pub(crate) fn mask_on(bus: &Bus, name: &str) -> Result<bool, Error> {
+    let arguments = vec![[name][..].into(), false.into(), false.into()];
+    call_manager(bus, "MaskUnitFiles", arguments)
+        // Return `Ok(true)` if the unit is already masked
+        .map(|items| is_disabled(&items))
+}
+
+/// Communicates with dbus to obtain a list of unit files and returns them as a `Vec<SystemdUnit>`. The runtime state
+/// and description of each unit is collected with a single `ListUnits` call, rather than querying each unit.
+pub fn list_unit_files(bustype: dbus::BusType) -> Result<Vec<SystemdUnit>, Error> {
+    let mut units = list_unit_files_on(&Bus::from(bustype), bustype)?;
+    describe_unloaded_units(&mut units, "");
+    Ok(units)
+}
+
+/// Lists the unit files of the manager that is reached through the bus. The units are marked as belonging to the
+/// given type of bus, which is the session bus if the manager is a user's manager.
+pub(crate) fn list_unit_files_on(bus: &Bus, bustype: dbus::BusType) -> Result<Vec<SystemdUnit>, Error> {
+    let message = call_manager(bus, "ListUnitFiles", Vec::new())?;
+    let mut units = parse_message(&format!("{:?}", message), bustype);
+    apply_loaded_units(&mut units, list_units_on(bus)?);
+    Ok(units)
+}
+
+/// Finds the unit file with the given name, such as `sshd.service`, among the unit files of the given bus.
+pub fn find_unit(bustype: dbus::BusType, name: &str) -> Result<SystemdUnit, Error> {
+    list_unit_files(bustype)?.into_iter()
+        .find(|unit| unit.name == name)
+        .ok_or_else(|| Error::NoSuchUnit(name.to_owned()))
+}
 
-    /// Takes the unit pathname of a service and enables it via dbus.
-    /// If dbus replies with `[Bool(true), Array([], "(sss)")]`, the service is already enabled.
-    fn enable(&self) -> Result<bool, String> {
-        let mut message = dbus_message!("EnableUnitFiles");
-        message.append_items(&[[self.name.as_str()][..].into(), false.into(), true.into()]);
-        dbus_connect!(message, self.bustype)
-            // Return `Ok(true)` if the unit is already enabled
-            .map(|reply| is_enabled(&reply.get_items()))
-            // Return `Err` if the unit could not be enabled.
-            .map_err(|reply| reply.to_string())
+/// Loads the unit with the given name, such as `sshd.service` or `getty@tty1.service`, directly from the manager of
+/// the bus, which also finds the instances of templates and transient units that have no unit file of their own.
+/// Units that are not known to systemd are reported as missing. The enablement of units that have no unit file is
+/// considered to be static.
+pub fn load_unit(bustype: dbus::BusType, name: &str) -> Result<SystemdUnit, Error> {
+    let utype = UnitType::from_name(name).ok_or_else(|| Error::NoSuchUnit(name.to_owned()))?;
+    let unit = SystemdUnit {
+        name: name.to_owned(), path: String::new(), description: String::new(), load_state: String::new(),
+        state: UnitState::Static, active_state: ActiveState::Inactive, sub_state: String::new(), utype: utype,
+        bustype: bustype, state_change: None, can_reload: None,
+    };
+    let unit = reload_unit(&unit)?;
+    if unit.load_state == "not-found" {
+        return Err(Error::NoSuchUnit(name.to_owned()))
     }
+    Ok(unit)
+}
+
+/// Collects the current description, enablement, and runtime state of a single unit, so that a unit can be
+/// refreshed after an action without listing every unit again.
+pub fn reload_unit(unit: &SystemdUnit) -> Result<SystemdUnit, Error> {
+    reload_unit_on(&Bus::from(unit.bustype), unit)
+}
 
-    /// Takes the unit pathname as input and disables it via dbus.
-    /// If dbus replies with `[Array([], "(sss)")]`, the service is already disabled.
-    fn disable(&self) -> Result<bool, String> {
-        let mut message = dbus_message!("DisableUnitFiles");
-        message.append_items(&[[self.name.as_str()][..].into(), false.into()]);
-        dbus_connect!(message, self.bustype)
-            // Return `Ok(true)` if the unit is already disabled
-            .map(|reply| is_disabled(&reply.get_items()))
-            // Return `Err` if the unit could not be disabled.
-            .map_err(|reply| reply.to_string())
+/// Reloads the state of the unit from the manager that is reached through the bus.
+pub(crate) fn reload_unit_on(bus: &Bus, unit: &SystemdUnit) -> Result<SystemdUnit, Error> {
+    let mut reloaded = unit.clone();
+    for (name, value) in &unit_properties_on(bus, &unit.name, "org.freedesktop.systemd1.Unit")? {
+        match (name.as_str(), value) {
+            ("Description", &MessageItem::Str(ref value)) => reloaded.description = value.clone(),
+            ("FragmentPath", &MessageItem::Str(ref value)) if !value.is_empty() => reloaded.path = value.clone(),
+            ("LoadState", &MessageItem::Str(ref value)) => reloaded.load_state = value.clone(),
+            ("ActiveState", &MessageItem::Str(ref value)) => reloaded.active_state = ActiveState::new(value),
+            ("SubState", &MessageItem::Str(ref value)) => reloaded.sub_state = value.clone(),
+            ("UnitFileState", &MessageItem::Str(ref value)) if !value.is_empty() => {
+                reloaded.state = UnitState::from_name(value)
+            }
+            // The timestamp is zero for units that have not changed their state since they were loaded.
+            ("StateChangeTimestamp", &MessageItem::UInt64(time)) => {
+                reloaded.state_change = if time == 0 { None } else { Some(time) }
+            }
+            ("CanReload", &MessageItem::Bool(can_reload)) => reloaded.can_reload = Some(can_reload),
+            _ => (),
+        }
     }
+    Ok(reloaded)
+}
+
+/// The runtime information of a unit that systemd has loaded, as reported by `ListUnits`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadedUnit {
+    /// The name of the unit, such as `sshd.service`.
+    pub name: String,
+    /// The description of the unit.
+    pub description: String,
+    /// Whether the unit file was loaded, such as `loaded` or `not-found`.
+    pub load_state: String,
+    /// Whether the unit is running, such as `active` or `failed`.
+    pub active_state: String,
+    /// The type-specific state of the unit, such as `running` or `dead`.
+    pub sub_state: String,
+}
+
+/// Communicates with dbus to obtain the runtime information of every unit that is currently loaded.
+pub fn list_units(bustype: dbus::BusType) -> Result<Vec<LoadedUnit>, Error> {
+    list_units_on(&Bus::from(bustype))
+}
+
+/// Lists the loaded units of the manager that is reached through the bus.
+fn list_units_on(bus: &Bus) -> Result<Vec<LoadedUnit>, Error> {
+    call_manager(bus, "ListUnits", Vec::new()).map(|items| parse_units(&items))
+}
+
+/// Takes the `a(ssssssouso)` reply of `ListUnits` and collects the name, description, and states of each unit.
+fn parse_units(items: &[MessageItem]) -> Vec<LoadedUnit> {
+    let units = match items.get(0) {
+        Some(&MessageItem::Array(ref units, _)) => units,
+        _ => return Vec::new(),
+    };
 
-    /// Takes a unit name as input and attempts to start it. It returns an error if an error occurs.
-    fn start(&self) -> Option<String> {
-        let mut message = dbus_message!("StartUnit");
-        message.append_items(&[self.name.as_str().into(), "fail".into()]);
-        // Return `Some(error)` if the unit could not be started, else return `None`.
-        dbus_connect!(message, self.bustype).err().map(|err| err.to_string())
+    units.iter()
+        .filter_map(|unit| match *unit {
+            MessageItem::Struct(ref fields) => {
+                // Collects the string field at the given position of the structure.
+                let field = |index: usize| match fields.get(index) {
+                    Some(&MessageItem::Str(ref value)) => Some(value.clone()),
+                    _ => None,
+                };
+                match (field(0), field(1), field(2), field(3), field(4)) {
+                    (Some(name), Some(description), Some(load_state), Some(active_state), Some(sub_state)) => {
+                        Some(LoadedUnit {
+                            name: name,
+                            description: description,
+                            load_state: load_state,
+                            active_state: active_state,
+                            sub_state: sub_state,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Applies the description and runtime states of the loaded units to the matching unit files.
+fn apply_loaded_units(units: &mut [SystemdUnit], loaded: Vec<LoadedUnit>) {
+    let loaded: HashMap<String, LoadedUnit> = loaded.into_iter().map(|unit| (unit.name.clone(), unit)).collect();
+    for unit in units.iter_mut() {
+        if let Some(loaded) = loaded.get(&unit.name) {
+            unit.description = loaded.description.clone();
+            unit.load_state = loaded.load_state.clone();
+            unit.active_state = ActiveState::new(&loaded.active_state);
+            unit.sub_state = loaded.sub_state.clone();
+        }
     }
+}
 
-    /// Takes a unit name as input and attempts to stop it.
-    fn stop(&self) -> Option<String> {
-        let mut message = dbus_message!("StopUnit");
-        message.append_items(&[self.name.as_str().into(), "fail".into()]);
-        // Return `Some(error)` if the unit could not be stopped, else return `None`.
-        dbus_connect!(message, self.bustype).err().map(|err| err.to_string())
+/// Fills in the description of the units that systemd has not loaded, which `ListUnits` does not return, from the
+/// `Description=` setting of their unit files. The unit files are read beneath the `root` directory, which is empty
+/// for the local machine.
+pub(crate) fn describe_unloaded_units(units: &mut [SystemdUnit], root: &str) {
+    for unit in units.iter_mut().filter(|unit| unit.description.is_empty()) {
+        if let Ok(info) = fs::read_to_string(format!("{}{}", root, unit.path)) {
+            if let Some(description) = get_unit_description(&info) {
+                unit.description = description.to_owned();
+            }
+        }
     }
 }
 
-/// Communicates with dbus to obtain a list of unit files and returns them as a `Vec<SystemdUnit>`.
-pub fn list_unit_files(bustype: dbus::BusType) -> Vec<SystemdUnit> {
-    let message = dbus_connect!(dbus_message!("ListUnitFiles"), bustype)
-        .expect("systemd-manager: unable to get dbus message from systemd").get_items();
-    parse_message(&format!("{:?}", message), bustype)
+/// The interval, in milliseconds, at which a watched manager checks whether it should still be watched.
+const WATCH_INTERVAL: i32 = 1000;
+
+/// Watches the manager that is reached through the bus for changes to the state of its units, sending the bus type
+/// and name of each unit that changed until `stop` is set. The manager is watched through a connection of its own,
+/// as the shared connection to the bus performs a single call at a time.
+pub(crate) fn watch_on(bus: &Bus, bustype: dbus::BusType, changed: &Sender<(dbus::BusType, String)>,
+        stop: &AtomicBool) -> Result<(), Error> {
+    let connection = bus.open().map_err(|why| Error::BusUnavailable(why.to_string()))?;
+    connection.add_match("type='signal',sender='org.freedesktop.systemd1',\
+        interface='org.freedesktop.DBus.Properties',member='PropertiesChanged'")?;
+    // systemd only emits the signals of its units once a client has subscribed to them.
+    connection.send_with_reply_and_block(dbus_message!("Subscribe"), 4000)?;
+
+    for item in connection.iter(WATCH_INTERVAL) {
+        if stop.load(Ordering::Relaxed) {
+            return Ok(())
+        }
+        if let ConnectionItem::Signal(message) = item {
+            let path = message.path().map(|path| path.to_string());
+            let interface = match message.get_items().into_iter().next() {
+                Some(MessageItem::Str(interface)) => interface,
+                _ => continue,
+            };
+            // The properties of the type-specific interfaces change along with those of the unit.
+            if interface != "org.freedesktop.systemd1.Unit" {
+                continue
+            }
+            if let Some(name) = path.as_ref().and_then(|path| unit_name_from_path(path)) {
+                if changed.send((bustype, name)).is_err() {
+                    return Ok(())
+                }
+            }
+        }
+    }
+    Err(Error::BusUnavailable(String::from("the connection to the bus was lost")))
+}
+
+/// The name of the unit that the object path belongs to, such as `sshd.service` for
+/// `/org/freedesktop/systemd1/unit/sshd_2eservice`, as systemd escapes the characters of the name that are not
+/// alphanumeric as `_xx`.
+fn unit_name_from_path(path: &str) -> Option<String> {
+    let escaped = path.trim_start_matches("/org/freedesktop/systemd1/unit/");
+    if escaped.len() == path.len() {
+        return None
+    }
+    let mut name = Vec::with_capacity(escaped.len());
+    let mut bytes = escaped.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte == b'_' {
+            let hex: Vec<u8> = bytes.by_ref().take(2).collect();
+            name.push(String::from_utf8(hex).ok().and_then(|hex| u8::from_str_radix(&hex, 16).ok())?);
+        } else {
+            name.push(byte);
+        }
+    }
+    String::from_utf8(name).ok()
+}
+
+/// Obtains all of the properties of the unit that belong to the given interface, such as
+/// `org.freedesktop.systemd1.Unit`. The unit is loaded by systemd if it is not already loaded.
+pub fn get_unit_properties(unit: &SystemdUnit, interface: &str) -> Result<BTreeMap<String, MessageItem>, Error> {
+    unit_properties_on(&Bus::from(unit.bustype), &unit.name, interface)
+}
+
+/// Obtains the properties of the named unit from the manager that is reached through the bus.
+pub(crate) fn unit_properties_on(bus: &Bus, name: &str, interface: &str)
+        -> Result<BTreeMap<String, MessageItem>, Error> {
+    let (name, interface) = (name.to_owned(), interface.to_owned());
+    connection::call(bus, move |connection| {
+        let mut message = dbus_message!("LoadUnit");
+        message.append_items(&[name.as_str().into()]);
+        let reply = connection.send_with_reply_and_block(message, 4000)?;
+        // The reply contains the object path of the unit, from which the properties are collected.
+        match reply.get_items().into_iter().next() {
+            Some(MessageItem::ObjectPath(path)) => {
+                dbus::Props::new(connection, "org.freedesktop.systemd1", path, interface.as_str(), 4000)
+                    .get_all()
+                    .map_err(Error::from)
+            }
+            _ => Err(Error::Parse(format!("unable to obtain the object path of {}", name))),
+        }
+    })
 }
 
 /// Takes the dbus message as input and maps the information to a `Vec<SystemdUnit>`.
@@ -109,7 +379,10 @@ fn parse_message(input: &str, bustype: dbus::BusType) -> Vec<SystemdUnit> {
         // The state of the unit can be determined by the first character in the `state`
         let state = UnitState::new(state);
         // Push the collected information into the `systemd_units` vector.
-        systemd_units.push(SystemdUnit{name: name, path: path, state: state, utype: utype, bustype: bustype});
+        // Units that are not loaded are inactive. Their runtime state is applied afterwards if they are loaded.
+        systemd_units.push(SystemdUnit{name: name, path: path, description: String::new(), load_state: String::new(),
+            state: state, active_state: ActiveState::Inactive, sub_state: String::from("dead"), utype: utype,
+            bustype: bustype, state_change: None, can_reload: None});
     }
 
     // Sort the list of units by their unit names using quickersort and then return the list.
@@ -126,3 +399,63 @@ fn is_enabled(items: &[MessageItem]) -> bool {
 fn is_disabled(items: &[MessageItem]) -> bool {
     format!("{:?}", items) == "[Array([], \"(sss)\")]"
 }
+
+#[test]
+fn test_parse_units() {
+    fn unit(name: &str, description: &str, active_state: &str, sub_state: &str) -> MessageItem {
+        MessageItem::Struct(vec![
+            MessageItem::Str(name.to_owned()),
+            MessageItem::Str(description.to_owned()),
+            MessageItem::Str("loaded".to_owned()),
+            MessageItem::Str(active_state.to_owned()),
+            MessageItem::Str(sub_state.to_owned()),
+            MessageItem::Str(String::new()),
+            MessageItem::ObjectPath("/org/freedesktop/systemd1/unit/sshd_2eservice".into()),
+            MessageItem::UInt32(0),
+            MessageItem::Str(String::new()),
+            MessageItem::ObjectPath("/".into()),
+        ])
+    }
+    let reply = [MessageItem::Array(vec![
+        unit("sshd.service", "OpenSSH Daemon", "active", "running"),
+        unit("cups.socket", "CUPS Scheduler", "failed", "failed"),
+    ], "(ssssssouso)".into())];
+    let units = parse_units(&reply);
+    assert_eq!(units.len(), 2);
+    assert_eq!(units[0], LoadedUnit {
+        name: String::from("sshd.service"),
+        description: String::from("OpenSSH Daemon"),
+        load_state: String::from("loaded"),
+        active_state: String::from("active"),
+        sub_state: String::from("running"),
+    });
+    assert_eq!(units[1].active_state, "failed");
+}
+
+#[test]
+fn test_unit_name_from_path() {
+    assert_eq!(unit_name_from_path("/org/freedesktop/systemd1/unit/sshd_2eservice"),
+        Some(String::from("sshd.service")));
+    assert_eq!(unit_name_from_path("/org/freedesktop/systemd1/unit/getty_40tty1_2eservice"),
+        Some(String::from("getty@tty1.service")));
+    assert_eq!(unit_name_from_path("/org/freedesktop/systemd1/job/4213"), None);
+}
+
+#[test]
+fn test_describe_unloaded_units() {
+    let root = std::env::temp_dir().join(format!("systemd-manager-{}", std::process::id()));
+    fs::create_dir_all(root.join("etc/systemd/system")).unwrap();
+    fs::write(root.join("etc/systemd/system/backup.service"), "[Unit]\nDescription=Nightly Backup\n").unwrap();
+
+    let unit = |name: &str, description: &str| SystemdUnit {
+        path: format!("/etc/systemd/system/{}", name),
+        load_state: String::new(),
+        ..SystemdUnit::loaded(name, description, UnitState::Disabled, ActiveState::Inactive)
+    };
+    let mut units = vec![unit("backup.service", ""), unit("sshd.service", "OpenSSH Daemon"), unit("gone.service", "")];
+    describe_unloaded_units(&mut units, root.to_str().unwrap());
+    fs::remove_dir_all(&root).unwrap();
+
+    let descriptions: Vec<&str> = units.iter().map(|unit| unit.description.as_str()).collect();
+    assert_eq!(descriptions, vec!["Nightly Backup", "OpenSSH Daemon", ""]);
+}
@@ -0,0 +1,190 @@
+//! Collects the state of a unit that failed to start, to explain why it failed.
+
+use std::collections::BTreeMap;
+use super::SystemdUnit;
+use super::backend::SystemdBackend;
+use super::dbus::dbus::MessageItem;
+use super::journal::JournalQuery;
+
+/// The number of journal lines that are collected from the unit's latest invocation.
+const JOURNAL_LINES: u32 = 20;
+
+/// The state of a unit that failed, which is collected to give the picture that `systemctl status` would give.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostics {
+    /// Whether the unit is running, such as `failed`.
+    pub active_state: String,
+    /// The type-specific state of the unit, such as `dead`.
+    pub sub_state: String,
+    /// The result of the unit's last run, such as `exit-code`.
+    pub result: Option<String>,
+    /// How the main process exited, as a `CLD_*` value.
+    pub exec_main_code: Option<i32>,
+    /// The exit status or signal of the main process.
+    pub exec_main_status: Option<i32>,
+    /// The conditions that were not met, which prevented the unit from starting.
+    pub failed_conditions: Vec<String>,
+    /// The asserts that were not met, which caused the unit to fail.
+    pub failed_asserts: Vec<String>,
+    /// The ID of the unit's latest invocation.
+    pub invocation: Option<String>,
+    /// The last lines of the journal for the unit's latest invocation.
+    pub journal: String,
+}
+
+impl Diagnostics {
+    /// Collects the diagnostics of the given unit from the manager and the journal of the backend.
+    pub fn collect(backend: &dyn SystemdBackend, unit: &SystemdUnit) -> Diagnostics {
+        let common = backend.unit_properties(unit, "org.freedesktop.systemd1.Unit").unwrap_or_default();
+        let specific = backend.unit_properties(unit, unit.utype.interface()).unwrap_or_default();
+
+        let invocation = common.get("InvocationID").and_then(as_hex).filter(|id| !id.is_empty());
+        let journal = match invocation {
+            Some(ref id) => backend.journal(&JournalQuery::invocation(id, JOURNAL_LINES, unit.bustype)),
+            None => {
+                let mut query = unit.journal_query();
+                query.lines = Some(JOURNAL_LINES);
+                backend.journal(&query)
+            }
+        };
+
+        Diagnostics {
+            active_state: get_string(&common, "ActiveState").unwrap_or_default(),
+            sub_state: get_string(&common, "SubState").unwrap_or_default(),
+            result: get_string(&specific, "Result"),
+            exec_main_code: specific.get("ExecMainCode").and_then(as_i32),
+            exec_main_status: specific.get("ExecMainStatus").and_then(as_i32),
+            failed_conditions: common.get("Conditions").map_or_else(Vec::new, failed_checks),
+            failed_asserts: common.get("Asserts").map_or_else(Vec::new, failed_checks),
+            invocation: invocation,
+            journal: journal.unwrap_or_else(|why| format!("Unable to read the journal entry for {}: {}", unit.name, why)),
+        }
+    }
+
+    /// Formats the diagnostics as a human-readable report.
+    pub fn summary(&self) -> String {
+        let mut output = format!("Active: {} ({})\n", self.active_state, self.sub_state);
+        if let Some(ref result) = self.result {
+            output.push_str(&format!("Result: {}\n", result));
+        }
+        if let (Some(code), Some(status)) = (self.exec_main_code, self.exec_main_status) {
+            output.push_str(&format!("Main Process: {}\n", describe_exit(code, status)));
+        }
+        if !self.failed_conditions.is_empty() {
+            output.push_str("Failed Conditions:\n");
+            for condition in &self.failed_conditions {
+                output.push_str(&format!("    {}\n", condition));
+            }
+        }
+        if !self.failed_asserts.is_empty() {
+            output.push_str("Failed Asserts:\n");
+            for assert in &self.failed_asserts {
+                output.push_str(&format!("    {}\n", assert));
+            }
+        }
+        match self.invocation {
+            Some(ref id) => output.push_str(&format!("\nJournal of Invocation {}:\n", id)),
+            None => output.push_str("\nJournal:\n"),
+        }
+        output.push_str(&self.journal);
+        output
+    }
+}
+
+/// Obtains a string property from a map of properties.
+fn get_string(properties: &BTreeMap<String, MessageItem>, name: &str) -> Option<String> {
+    match properties.get(name) {
+        Some(&MessageItem::Str(ref value)) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+/// Obtains the value of an `i` property.
+fn as_i32(item: &MessageItem) -> Option<i32> {
+    match *item {
+        MessageItem::Int32(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// Converts an `ay` property, such as the `InvocationID`, into a hexadecimal string.
+fn as_hex(item: &MessageItem) -> Option<String> {
+    match *item {
+        MessageItem::Array(ref bytes, _) => bytes.iter()
+            .map(|byte| match *byte {
+                MessageItem::Byte(byte) => Some(format!("{:02x}", byte)),
+                _ => None,
+            })
+            .collect(),
+        _ => None,
+    }
+}
+
+/// Takes the `a(sbbsi)` value of the `Conditions` or `Asserts` properties and collects the checks that failed,
+/// formatted as they would be written in the unit file, such as `ConditionPathExists=!/etc/example`.
+/// Each check contains its type, whether it is a triggering check, whether it is negated, its parameter, and
+/// its state, where a negative state signifies that the check has failed.
+fn failed_checks(item: &MessageItem) -> Vec<String> {
+    let checks = match *item {
+        MessageItem::Array(ref checks, _) => checks,
+        _ => return Vec::new(),
+    };
+
+    checks.iter()
+        .filter_map(|check| match *check {
+            MessageItem::Struct(ref fields) => match (fields.get(0), fields.get(1), fields.get(2), fields.get(3),
+                    fields.get(4)) {
+                (Some(&MessageItem::Str(ref kind)), Some(&MessageItem::Bool(trigger)),
+                        Some(&MessageItem::Bool(negate)), Some(&MessageItem::Str(ref parameter)),
+                        Some(&MessageItem::Int32(state))) if state < 0 => {
+                    Some(format!("{}={}{}{}", kind, if trigger { "|" } else { "" }, if negate { "!" } else { "" },
+                        parameter))
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Describes how the main process exited, given the `ExecMainCode` (a `CLD_*` value) and `ExecMainStatus`.
+fn describe_exit(code: i32, status: i32) -> String {
+    match code {
+        1 => format!("exited, status={}", status),
+        2 => format!("killed, signal={}", status),
+        3 => format!("dumped core, signal={}", status),
+        _ => String::from("not started"),
+    }
+}
+
+#[test]
+fn test_failed_checks() {
+    fn check(kind: &str, trigger: bool, negate: bool, parameter: &str, state: i32) -> MessageItem {
+        MessageItem::Struct(vec![
+            MessageItem::Str(kind.to_owned()),
+            MessageItem::Bool(trigger),
+            MessageItem::Bool(negate),
+            MessageItem::Str(parameter.to_owned()),
+            MessageItem::Int32(state),
+        ])
+    }
+    let conditions = MessageItem::Array(vec![
+        check("ConditionPathExists", false, true, "/etc/example", -1),
+        check("ConditionVirtualization", true, false, "container", 1),
+        check("ConditionHost", false, false, "example", 0),
+    ], "(sbbsi)".into());
+    assert_eq!(failed_checks(&conditions), vec!["ConditionPathExists=!/etc/example"]);
+}
+
+#[test]
+fn test_as_hex() {
+    let id = MessageItem::Array(vec![MessageItem::Byte(1), MessageItem::Byte(171)], "y".into());
+    assert_eq!(as_hex(&id), Some(String::from("01ab")));
+}
+
+#[test]
+fn test_describe_exit() {
+    assert_eq!(describe_exit(1, 203), "exited, status=203");
+    assert_eq!(describe_exit(2, 9), "killed, signal=9");
+    assert_eq!(describe_exit(0, 0), "not started");
+}
@@ -0,0 +1,90 @@
+use std::error;
+use std::fmt;
+use std::io;
+use super::dbus::dbus;
+
+/// The reasons that an operation upon systemd, or upon one of its commands, can fail.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The user is not authorized to perform the operation, or declined to authorize it.
+    AccessDenied(String),
+    /// The unit, or its unit file, does not exist.
+    NoSuchUnit(String),
+    /// No connection could be made to the bus, or the connection was lost.
+    BusUnavailable(String),
+    /// The bus did not reply in time.
+    Timeout,
+    /// systemd, or the command that was run, was unable to perform the operation.
+    JobFailed(String),
+    /// A reply from the bus, or the output of a command, could not be understood.
+    Parse(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::AccessDenied(ref why) => write!(f, "access denied: {}", why),
+            Error::NoSuchUnit(ref why) => write!(f, "no such unit: {}", why),
+            Error::BusUnavailable(ref why) => write!(f, "bus unavailable: {}", why),
+            Error::Timeout => write!(f, "timed out waiting for a reply"),
+            Error::JobFailed(ref why) => write!(f, "operation failed: {}", why),
+            Error::Parse(ref why) => write!(f, "unable to parse: {}", why),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<dbus::Error> for Error {
+    fn from(error: dbus::Error) -> Error {
+        from_dbus_error(error.name().unwrap_or_default(), error.message().unwrap_or_default())
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        match error.kind() {
+            io::ErrorKind::PermissionDenied => Error::AccessDenied(error.to_string()),
+            _ => Error::JobFailed(error.to_string()),
+        }
+    }
+}
+
+/// Classifies an error that was replied by the bus, or by systemd, based on the name of the error.
+fn from_dbus_error(name: &str, message: &str) -> Error {
+    let message = message.to_owned();
+    match name {
+        "org.freedesktop.DBus.Error.AccessDenied"
+            | "org.freedesktop.DBus.Error.AuthFailed"
+            | "org.freedesktop.DBus.Error.InteractiveAuthorizationRequired" => Error::AccessDenied(message),
+        "org.freedesktop.systemd1.NoSuchUnit"
+            | "org.freedesktop.systemd1.LoadFailed"
+            | "org.freedesktop.DBus.Error.FileNotFound" => Error::NoSuchUnit(message),
+        "org.freedesktop.DBus.Error.Disconnected"
+            | "org.freedesktop.DBus.Error.NoServer"
+            | "org.freedesktop.DBus.Error.ServiceUnknown"
+            | "org.freedesktop.DBus.Error.NameHasNoOwner" => Error::BusUnavailable(message),
+        "org.freedesktop.DBus.Error.NoReply"
+            | "org.freedesktop.DBus.Error.Timeout"
+            | "org.freedesktop.DBus.Error.TimedOut" => Error::Timeout,
+        _ if message.is_empty() => Error::JobFailed(name.to_owned()),
+        _ => Error::JobFailed(message),
+    }
+}
+
+#[test]
+fn test_boxed_error() {
+    let boxed: Box<dyn error::Error> = Box::new(Error::Timeout);
+    assert_eq!(boxed.to_string(), "timed out waiting for a reply");
+}
+
+#[test]
+fn test_from_dbus_error() {
+    assert_eq!(from_dbus_error("org.freedesktop.DBus.Error.AccessDenied", "Permission denied"),
+        Error::AccessDenied(String::from("Permission denied")));
+    assert_eq!(from_dbus_error("org.freedesktop.systemd1.NoSuchUnit", "Unit example.service not found."),
+        Error::NoSuchUnit(String::from("Unit example.service not found.")));
+    assert_eq!(from_dbus_error("org.freedesktop.DBus.Error.NoReply", "Did not receive a reply."), Error::Timeout);
+    assert_eq!(from_dbus_error("org.freedesktop.systemd1.UnitMasked", ""),
+        Error::JobFailed(String::from("org.freedesktop.systemd1.UnitMasked")));
+}
@@ -0,0 +1,382 @@
+//! Reads, exports, and maintains the journal through `journalctl`.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use super::Error;
+use super::dbus::dbus::BusType as BusType;
+
+/// The formats that journal entries can be written out as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JournalFormat {
+    /// Plain text, as displayed by `journalctl`.
+    Text,
+    /// One JSON object per entry.
+    Json,
+    /// The binary-safe journal export format.
+    Export,
+}
+
+impl JournalFormat {
+    /// Takes the ID of a format selection and converts it into a `JournalFormat`, defaulting to plain text.
+    pub fn from_id(id: &str) -> JournalFormat {
+        match id {
+            "json" => JournalFormat::Json,
+            "export" => JournalFormat::Export,
+            _ => JournalFormat::Text,
+        }
+    }
+
+    /// The file extension that is suggested when saving entries in this format.
+    pub fn extension(&self) -> &'static str {
+        match *self {
+            JournalFormat::Text => "log",
+            JournalFormat::Json => "json",
+            JournalFormat::Export => "journal",
+        }
+    }
+
+    /// The value that `journalctl --output` expects for this format.
+    fn output_mode(&self) -> &'static str {
+        match *self {
+            JournalFormat::Text => "short",
+            JournalFormat::Json => "json",
+            JournalFormat::Export => "export",
+        }
+    }
+}
+
+/// Describes which journal entries are to be read, so that the journal views and exports share the same filter.
+#[derive(Clone, Debug)]
+pub struct JournalQuery {
+    /// The units whose entries are matched. Every entry is matched if there are none.
+    pub units: Vec<String>,
+    /// Whether the journal of the system or of the user is read.
+    pub bustype: BusType,
+    /// The container whose journal is read, rather than the journal of the local machine.
+    pub machine: Option<String>,
+    /// The user whose user units are matched, rather than those of the current user, as the journal of another
+    /// user's manager is only readable by root.
+    pub user: Option<u32>,
+    /// The invocation ID whose entries are matched.
+    pub invocation: Option<String>,
+    /// The number of most recent entries to read.
+    pub lines: Option<u32>,
+    /// Whether only kernel messages are matched.
+    pub kernel: bool,
+    /// Whether only the entries of the current boot are matched.
+    pub current_boot: bool,
+    /// Whether the newest entries are listed first.
+    pub reverse: bool,
+}
+
+impl JournalQuery {
+    /// Creates a query for the current boot's entries of a single unit, newest entries first.
+    pub fn unit(name: &str, bustype: BusType) -> JournalQuery {
+        JournalQuery::units(vec![name.to_owned()], bustype)
+    }
+
+    /// Creates a query for the current boot's entries of several units merged together, newest entries first.
+    pub fn units(units: Vec<String>, bustype: BusType) -> JournalQuery {
+        JournalQuery { units: units, ..JournalQuery::boot(bustype) }
+    }
+
+    /// Creates a query for every entry of the current boot, newest entries first.
+    pub fn boot(bustype: BusType) -> JournalQuery {
+        JournalQuery {
+            units: Vec::new(),
+            bustype: bustype,
+            machine: None,
+            user: None,
+            invocation: None,
+            lines: None,
+            kernel: false,
+            current_boot: true,
+            reverse: true,
+        }
+    }
+
+    /// Creates a query for the kernel messages of the current boot, newest entries first.
+    pub fn kernel() -> JournalQuery {
+        JournalQuery { kernel: true, ..JournalQuery::boot(BusType::System) }
+    }
+
+    /// Creates a query for the last lines that were logged by a single invocation of a unit, oldest entries first.
+    pub fn invocation(id: &str, lines: u32, bustype: BusType) -> JournalQuery {
+        JournalQuery {
+            invocation: Some(id.to_owned()),
+            lines: Some(lines),
+            reverse: false,
+            ..JournalQuery::boot(bustype)
+        }
+    }
+
+    /// Collects the arguments that will be handed to `journalctl` to perform the query.
+    pub fn args(&self, format: JournalFormat) -> Vec<String> {
+        let mut args = Vec::new();
+        // The entries of another user's units are matched by their user ID, rather than with `--user`.
+        match (self.bustype, self.user) {
+            (BusType::Session, None) => args.push("--user".to_owned()),
+            (_, Some(uid)) => args.push(format!("_UID={}", uid)),
+            _ => (),
+        }
+        if let Some(ref machine) = self.machine {
+            args.push("-M".to_owned());
+            args.push(machine.clone());
+        }
+        if self.kernel {
+            args.push("-k".to_owned());
+        }
+        if self.current_boot {
+            args.push("-b".to_owned());
+        }
+        if self.reverse {
+            args.push("-r".to_owned());
+        }
+        if let Some(lines) = self.lines {
+            args.push("-n".to_owned());
+            args.push(lines.to_string());
+        }
+        for unit in &self.units {
+            args.push(if self.user.is_some() { "--user-unit" } else { "-u" }.to_owned());
+            args.push(unit.clone());
+        }
+        if let Some(ref id) = self.invocation {
+            // Entries of the unit's processes, of systemd about the unit, and of user units, are all matched.
+            args.push(format!("_SYSTEMD_INVOCATION_ID={}", id));
+            args.push("+".to_owned());
+            args.push(format!("INVOCATION_ID={}", id));
+            args.push("+".to_owned());
+            args.push(format!("USER_INVOCATION_ID={}", id));
+        }
+        args.push("-o".to_owned());
+        args.push(format.output_mode().to_owned());
+        args
+    }
+
+    /// Runs the query and returns the entries as plain text.
+    pub fn read(&self) -> Result<String, Error> {
+        self.read_as(JournalFormat::Text)
+    }
+
+    /// Runs the query and returns the entries in the requested format.
+    pub fn read_as(&self, format: JournalFormat) -> Result<String, Error> {
+        let output = Command::new("journalctl").args(&self.args(format)).output()?;
+        if !output.status.success() {
+            return Err(Error::JobFailed(String::from_utf8_lossy(&output.stderr).trim().to_owned()));
+        }
+        // Collect the output of the journal as a `String`
+        String::from_utf8(output.stdout).map_err(|why| Error::Parse(why.to_string()))
+    }
+
+    /// Runs the query and writes the entries to the given file in the requested format. Entries are always
+    /// written oldest first, as that is the order that the JSON and export formats are expected to be read in.
+    pub fn export(&self, format: JournalFormat, path: &Path) -> Result<(), Error> {
+        let mut query = self.clone();
+        query.reverse = false;
+        let file = File::create(path)?;
+        let status = Command::new("journalctl")
+            .args(&query.args(format))
+            .stdout(Stdio::from(file))
+            .status()?;
+        if status.success() {
+            Ok(())
+        } else {
+            Err(Error::JobFailed(format!("journalctl exited with {}", status)))
+        }
+    }
+}
+
+/// The directories that the persistent and volatile journal files are stored in.
+pub const JOURNAL_DIRECTORIES: [&'static str; 2] = ["/var/log/journal", "/run/log/journal"];
+
+/// The amount of disk space that the journal files within a directory take up.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalUsage {
+    /// The directory that contains the journal files.
+    pub directory: String,
+    /// The human-readable size of the journal files, such as `1.2G`.
+    pub usage: String,
+}
+
+/// Runs `journalctl --disk-usage` against each journal directory that exists to obtain their disk usage.
+pub fn disk_usage() -> Result<Vec<JournalUsage>, Error> {
+    JOURNAL_DIRECTORIES.iter()
+        .filter(|directory| Path::new(directory).is_dir())
+        .map(|directory| {
+            let output = Command::new("journalctl").arg("-D").arg(directory).arg("--disk-usage").output()?;
+            // Collect the size from the output, else mark the usage as unavailable.
+            let usage = parse_disk_usage(&String::from_utf8_lossy(&output.stdout))
+                .map_or_else(|| "N/A".to_owned(), String::from);
+            Ok(JournalUsage { directory: directory.to_string(), usage: usage })
+        })
+        .collect()
+}
+
+/// The maintenance actions that can be performed on the journal files.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JournalMaintenance {
+    /// Removes archived journal files until they take up no more than the given size, such as `500M`.
+    VacuumSize(String),
+    /// Removes archived journal files that only contain entries older than the given time, such as `2weeks`.
+    VacuumTime(String),
+    /// Archives the active journal files and starts new ones.
+    Rotate,
+}
+
+impl JournalMaintenance {
+    /// Collects the arguments that will be handed to `journalctl` to perform the action.
+    fn args(&self) -> Vec<String> {
+        match *self {
+            JournalMaintenance::VacuumSize(ref size) => vec![format!("--vacuum-size={}", size)],
+            JournalMaintenance::VacuumTime(ref time) => vec![format!("--vacuum-time={}", time)],
+            JournalMaintenance::Rotate => vec!["--rotate".to_owned()],
+        }
+    }
+
+    /// A short description of the action, such as "journal vacuum to 500M".
+    pub fn description(&self) -> String {
+        match *self {
+            JournalMaintenance::VacuumSize(ref size) => format!("journal vacuum to {}", size),
+            JournalMaintenance::VacuumTime(ref time) => format!("journal vacuum of entries older than {}", time),
+            JournalMaintenance::Rotate => String::from("journal rotation"),
+        }
+    }
+
+    /// Performs the action, going through `pkexec` to obtain authorization if we are not already running as root.
+    pub fn run(&self) -> Result<(), Error> {
+        match *self {
+            JournalMaintenance::VacuumSize(ref amount) | JournalMaintenance::VacuumTime(ref amount) => {
+                if !is_valid_amount(amount) {
+                    return Err(Error::Parse(format!("'{}' is not a valid amount", amount)));
+                }
+            }
+            JournalMaintenance::Rotate => (),
+        }
+
+        let root = is_root();
+        let mut command = if root {
+            Command::new("journalctl")
+        } else {
+            let mut command = Command::new("pkexec");
+            command.arg("journalctl");
+            command
+        };
+
+        let output = command.args(&self.args()).output()?;
+        // Return the standard error of the command if it was not successful.
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+        match output.status.code() {
+            Some(0) => Ok(()),
+            // `pkexec` exits with 126 if the authorization dialog was dismissed, and 127 if it was not authorized.
+            Some(126) | Some(127) if !root => Err(Error::AccessDenied(stderr)),
+            _ => Err(Error::JobFailed(stderr)),
+        }
+    }
+}
+
+/// Parses the stdout of `journalctl --disk-usage` to obtain the human-readable size that follows "take up".
+///
+/// > Example Output: "Archived and active journals take up 1.2G in the file system."
+fn parse_disk_usage(stdout: &str) -> Option<&str> {
+    stdout.split_whitespace().skip_while(|word| *word != "up").nth(1)
+}
+
+/// Returns true if the input is a number followed by an optional unit, such as `500M` or `2weeks`.
+fn is_valid_amount(input: &str) -> bool {
+    let digits = input.chars().take_while(|x| x.is_digit(10)).count();
+    digits > 0 && input.chars().skip(digits).all(|x| x.is_alphabetic())
+}
+
+/// Returns true if the effective user of this process is root.
+fn is_root() -> bool {
+    let mut status = String::new();
+    File::open("/proc/self/status")
+        .and_then(|mut file| file.read_to_string(&mut status))
+        .ok()
+        .and_then(|_| parse_effective_uid(&status))
+        .map_or(false, |uid| uid == 0)
+}
+
+/// Parses the contents of `/proc/self/status` to obtain the effective UID, which is the second field of `Uid:`.
+fn parse_effective_uid(status: &str) -> Option<u32> {
+    status.lines()
+        .find(|line| line.starts_with("Uid:"))
+        .and_then(|line| line.split_whitespace().nth(2))
+        .and_then(|uid| uid.parse::<u32>().ok())
+}
+
+#[test]
+fn test_journal_unit_args() {
+    let query = JournalQuery::unit("sshd.service", BusType::System);
+    assert_eq!(query.args(JournalFormat::Text), vec!["-b", "-r", "-u", "sshd.service", "-o", "short"]);
+    let query = JournalQuery::unit("pulseaudio.service", BusType::Session);
+    assert_eq!(query.args(JournalFormat::Json), vec!["--user", "-b", "-r", "-u", "pulseaudio.service", "-o", "json"]);
+    let mut query = JournalQuery::unit("nginx.service", BusType::System);
+    query.machine = Some(String::from("web"));
+    assert_eq!(query.args(JournalFormat::Text), vec!["-M", "web", "-b", "-r", "-u", "nginx.service", "-o", "short"]);
+    let mut query = JournalQuery::unit("syncthing.service", BusType::Session);
+    query.user = Some(1000);
+    assert_eq!(query.args(JournalFormat::Text),
+        vec!["_UID=1000", "-b", "-r", "--user-unit", "syncthing.service", "-o", "short"]);
+}
+
+#[test]
+fn test_journal_system_args() {
+    assert_eq!(JournalQuery::kernel().args(JournalFormat::Text), vec!["-k", "-b", "-r", "-o", "short"]);
+    assert_eq!(JournalQuery::boot(BusType::Session).args(JournalFormat::Text), vec!["--user", "-b", "-r", "-o", "short"]);
+    let units = vec!["sshd.service".to_owned(), "nginx.service".to_owned()];
+    assert_eq!(
+        JournalQuery::units(units, BusType::System).args(JournalFormat::Export),
+        vec!["-b", "-r", "-u", "sshd.service", "-u", "nginx.service", "-o", "export"]
+    );
+}
+
+#[test]
+fn test_journal_invocation_args() {
+    let query = JournalQuery::invocation("0123abcd", 20, BusType::System);
+    assert_eq!(query.args(JournalFormat::Text), vec![
+        "-b", "-n", "20", "_SYSTEMD_INVOCATION_ID=0123abcd", "+", "INVOCATION_ID=0123abcd", "+",
+        "USER_INVOCATION_ID=0123abcd", "-o", "short"
+    ]);
+}
+
+#[test]
+fn test_journal_format_from_id() {
+    assert_eq!(JournalFormat::from_id("json"), JournalFormat::Json);
+    assert_eq!(JournalFormat::from_id("export"), JournalFormat::Export);
+    assert_eq!(JournalFormat::from_id("text"), JournalFormat::Text);
+    assert_eq!(JournalFormat::Export.extension(), "journal");
+}
+
+#[test]
+fn test_parse_disk_usage() {
+    let input = "Archived and active journals take up 1.2G in the file system.";
+    assert_eq!(parse_disk_usage(input), Some("1.2G"));
+    assert_eq!(parse_disk_usage("No journal files were found."), None);
+}
+
+#[test]
+fn test_is_valid_amount() {
+    assert!(is_valid_amount("500M"));
+    assert!(is_valid_amount("2weeks"));
+    assert!(is_valid_amount("1024"));
+    assert!(!is_valid_amount("M500"));
+    assert!(!is_valid_amount("1; rm -rf /"));
+    assert!(!is_valid_amount(""));
+}
+
+#[test]
+fn test_parse_effective_uid() {
+    let input = "Name:\tsystemd-manager\nUid:\t1000\t0\t0\t0\nGid:\t1000\t1000\t1000\t1000";
+    assert_eq!(parse_effective_uid(input), Some(0));
+    assert_eq!(parse_effective_uid("Name:\tsystemd-manager"), None);
+}
+
+#[test]
+fn test_journal_maintenance_args() {
+    assert_eq!(JournalMaintenance::VacuumSize("500M".to_owned()).args(), vec!["--vacuum-size=500M"]);
+    assert_eq!(JournalMaintenance::VacuumTime("2weeks".to_owned()).args(), vec!["--vacuum-time=2weeks"]);
+    assert_eq!(JournalMaintenance::Rotate.args(), vec!["--rotate"]);
+}
@@ -0,0 +1,152 @@
+//! Lists the users that are logged in through `systemd-logind`, through its D-Bus API,
+//! `org.freedesktop.login1.Manager`, and manages the units of their user managers.
+
+use std::collections::BTreeMap;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::Sender;
+use super::analyze::Analyze;
+use super::backend::{escape_address_value, AddressBackend, SystemdBackend};
+use super::connection::{self, Bus};
+use super::dbus;
+use super::dbus::dbus::{BusType, Message, MessageItem};
+use super::journal::JournalQuery;
+use super::{Error, SystemdUnit};
+
+/// A user that has at least one session, and therefore a running user manager.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoggedInUser {
+    /// The user ID of the user.
+    pub uid: u32,
+    /// The login name of the user.
+    pub name: String,
+}
+
+/// Lists the users that are logged in, sorted by their user IDs.
+pub fn list_users() -> Result<Vec<LoggedInUser>, Error> {
+    let items = connection::call(&Bus::Type(BusType::System), |connection| {
+        let message = Message::new_method_call("org.freedesktop.login1", "/org/freedesktop/login1",
+            "org.freedesktop.login1.Manager", "ListUsers").map_err(Error::Parse)?;
+        connection.send_with_reply_and_block(message, 4000)
+            .map(|reply| reply.get_items())
+            .map_err(Error::from)
+    })?;
+
+    let mut users = parse_users(&items);
+    users.sort_by_key(|user| user.uid);
+    Ok(users)
+}
+
+/// Takes the `a(uso)` reply of `ListUsers` and collects the user ID and name of each user.
+fn parse_users(items: &[MessageItem]) -> Vec<LoggedInUser> {
+    let users = match items.get(0) {
+        Some(&MessageItem::Array(ref users, _)) => users,
+        _ => return Vec::new(),
+    };
+
+    users.iter()
+        .filter_map(|user| match *user {
+            MessageItem::Struct(ref fields) => match (fields.get(0), fields.get(1)) {
+                (Some(&MessageItem::UInt32(uid)), Some(&MessageItem::Str(ref name))) => {
+                    Some(LoggedInUser { uid: uid, name: name.clone() })
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// The user manager of another logged-in user, as managed by `systemctl --user --machine=USER@.host`. The manager
+/// is reached by running `systemd-stdio-bridge` within the user's manager through `systemd-run`, and the journal of
+/// the user's units is matched by the user's ID, which requires root.
+#[derive(Clone, Debug)]
+pub struct UserBackend {
+    user: LoggedInUser,
+    bus: AddressBackend,
+}
+
+impl UserBackend {
+    /// Manages the units of the user's manager.
+    pub fn new(user: &LoggedInUser) -> UserBackend {
+        let address = format!("unixexec:path=systemd-run,argv1=-M{}%40.host,argv2=--user,argv3=-PGq,argv4=--wait,\
+            argv5=-pIgnoreSIGPIPE=no,argv6=--,argv7=systemd-stdio-bridge,argv8=--user",
+            escape_address_value(&user.name));
+        UserBackend { user: user.clone(), bus: AddressBackend::new(&address, BusType::Session) }
+    }
+}
+
+impl SystemdBackend for UserBackend {
+    fn name(&self) -> String { format!("the user manager of {}", self.user.name) }
+
+    /// The units that systemd has not loaded are described by their unit files, which are on the local file system.
+    fn list_unit_files(&self) -> Result<Vec<SystemdUnit>, Error> {
+        let mut units = self.bus.list_unit_files()?;
+        dbus::describe_unloaded_units(&mut units, "");
+        Ok(units)
+    }
+
+    fn reload_unit(&self, unit: &SystemdUnit) -> Result<SystemdUnit, Error> { self.bus.reload_unit(unit) }
+
+    fn unit_properties(&self, unit: &SystemdUnit, interface: &str) -> Result<BTreeMap<String, MessageItem>, Error> {
+        self.bus.unit_properties(unit, interface)
+    }
+
+    fn enable(&self, unit: &SystemdUnit) -> Result<bool, Error> { self.bus.enable(unit) }
+
+    fn disable(&self, unit: &SystemdUnit) -> Result<bool, Error> { self.bus.disable(unit) }
+
+    fn start(&self, unit: &SystemdUnit) -> Result<(), Error> { self.bus.start(unit) }
+
+    fn stop(&self, unit: &SystemdUnit) -> Result<(), Error> { self.bus.stop(unit) }
+
+    fn restart(&self, unit: &SystemdUnit) -> Result<(), Error> { self.bus.restart(unit) }
+
+    fn reload(&self, unit: &SystemdUnit) -> Result<(), Error> { self.bus.reload(unit) }
+
+    fn mask(&self, unit: &SystemdUnit) -> Result<bool, Error> { self.bus.mask(unit) }
+
+    /// The unit files of the user are on the local file system, such as in `~/.config/systemd/user`.
+    fn unit_file(&self, unit: &SystemdUnit) -> Result<String, Error> { unit.get_info() }
+
+    fn save_unit_file(&self, unit: &SystemdUnit, contents: &str) -> Result<(), Error> { unit.save_info(contents) }
+
+    fn dependencies(&self, unit: &SystemdUnit) -> Result<String, Error> { self.bus.dependencies(unit) }
+
+    fn journal(&self, query: &JournalQuery) -> Result<String, Error> { self.scope_journal(query)?.read() }
+
+    fn scope_journal(&self, query: &JournalQuery) -> Result<JournalQuery, Error> {
+        let mut query = query.clone();
+        query.user = Some(self.user.uid);
+        Ok(query)
+    }
+
+    fn blame(&self) -> Result<Vec<Analyze>, Error> { self.bus.blame() }
+
+    fn boot_time(&self) -> Result<(String, String, String), Error> { self.bus.boot_time() }
+
+    fn watch(&self, changed: Sender<(BusType, String)>, stop: &AtomicBool) -> Result<(), Error> {
+        self.bus.watch(changed, stop)
+    }
+}
+
+#[test]
+fn test_parse_users() {
+    let user = |uid: u32, name: &str| MessageItem::Struct(vec![
+        MessageItem::UInt32(uid),
+        MessageItem::Str(name.to_owned()),
+        MessageItem::ObjectPath(format!("/org/freedesktop/login1/user/_{}", uid).into()),
+    ]);
+    let reply = [MessageItem::Array(vec![user(1000, "alice"), user(1001, "bob")], "(uso)".into())];
+    assert_eq!(parse_users(&reply), vec![
+        LoggedInUser { uid: 1000, name: String::from("alice") },
+        LoggedInUser { uid: 1001, name: String::from("bob") },
+    ]);
+}
+
+#[test]
+fn test_user_backend() {
+    let backend = UserBackend::new(&LoggedInUser { uid: 1000, name: String::from("alice") });
+    assert_eq!(backend.name(), "the user manager of alice");
+    assert_eq!(backend.bus.address(), "unixexec:path=systemd-run,argv1=-Malice%40.host,argv2=--user,argv3=-PGq,\
+        argv4=--wait,argv5=-pIgnoreSIGPIPE=no,argv6=--,argv7=systemd-stdio-bridge,argv8=--user");
+}
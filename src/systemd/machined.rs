@@ -0,0 +1,322 @@
+//! Lists and controls the containers and virtual machines that are registered with `systemd-machined`, through its
+//! D-Bus API, `org.freedesktop.machine1.Manager`, and manages the units of their own systemd managers.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::sync::atomic::AtomicBool;
+use std::sync::mpsc::Sender;
+use super::analyze::Analyze;
+use super::backend::{AddressBackend, SystemdBackend};
+use super::connection::{self, Bus};
+use super::dbus;
+use super::dbus::dbus::{BusType, Message, MessageItem, Props};
+use super::journal::JournalQuery;
+use super::{Error, SystemdUnit};
+
+/// The signal that asks the init system of a container to power off, `SIGRTMIN+4`, as sent by `machinectl poweroff`.
+const POWEROFF_SIGNAL: i32 = 38;
+
+/// A container or virtual machine that is known to `systemd-machined`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Machine {
+    /// The name of the machine.
+    pub name: String,
+    /// Whether the machine is a `container` or a `vm`.
+    pub class: String,
+    /// The service that registered the machine, such as `systemd-nspawn`, which is empty for stopped machines.
+    pub service: String,
+    /// The state of the machine, such as `running`, or `stopped` for an image that is not running.
+    pub state: String,
+    /// The process ID of the machine's init process, if the machine is running.
+    pub leader: Option<u32>,
+}
+
+/// Calls a method of the machine manager on the system bus, returning the items of the reply.
+fn call_machined(method: &'static str, arguments: Vec<MessageItem>) -> Result<Vec<MessageItem>, Error> {
+    connection::call(&Bus::Type(BusType::System), move |connection| {
+        let mut message = Message::new_method_call("org.freedesktop.machine1", "/org/freedesktop/machine1",
+            "org.freedesktop.machine1.Manager", method).map_err(Error::Parse)?;
+        message.append_items(&arguments);
+        connection.send_with_reply_and_block(message, 4000)
+            .map(|reply| reply.get_items())
+            .map_err(Error::from)
+    })
+}
+
+/// Lists the running machines, along with the images of machines that are not running, sorted by their names.
+pub fn list_machines() -> Result<Vec<Machine>, Error> {
+    let mut machines = connection::call(&Bus::Type(BusType::System), |connection| {
+        let message = Message::new_method_call("org.freedesktop.machine1", "/org/freedesktop/machine1",
+            "org.freedesktop.machine1.Manager", "ListMachines").map_err(Error::Parse)?;
+        let reply = connection.send_with_reply_and_block(message, 4000)?;
+
+        // The state and leader of each machine are properties of the machine's object.
+        let mut machines = Vec::new();
+        for (name, class, service, path) in parse_machines(&reply.get_items()) {
+            let properties = Props::new(connection, "org.freedesktop.machine1", path,
+                "org.freedesktop.machine1.Machine", 4000).get_all()?;
+            machines.push(Machine {
+                name: name,
+                class: class,
+                service: service,
+                state: match properties.get("State") {
+                    Some(&MessageItem::Str(ref state)) => state.clone(),
+                    _ => String::from("running"),
+                },
+                leader: match properties.get("Leader") {
+                    Some(&MessageItem::UInt32(leader)) => Some(leader),
+                    _ => None,
+                },
+            });
+        }
+        Ok(machines)
+    })?;
+
+    // Images that are not running can be started, so they are listed as stopped machines.
+    let running: HashSet<String> = machines.iter().map(|machine| machine.name.clone()).collect();
+    for image in parse_images(&call_machined("ListImages", Vec::new())?) {
+        if !running.contains(&image) {
+            machines.push(Machine {
+                name: image,
+                class: String::from("container"),
+                service: String::new(),
+                state: String::from("stopped"),
+                leader: None,
+            });
+        }
+    }
+
+    machines.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(machines)
+}
+
+/// Starts the container from its image, through the `systemd-nspawn@.service` template as `machinectl start` does.
+pub fn start_machine(name: &str) -> Result<(), Error> {
+    dbus::start_on(&Bus::Type(BusType::System), &format!("systemd-nspawn@{}.service", escape_instance(name)))
+}
+
+/// Escapes the name of a machine for the instance of a unit name, as `systemd-escape` does, since systemd unescapes
+/// the instance for `%i`, which turns a `-` into a `/`. Only alphanumeric characters, `:`, `_`, and a `.` that does
+/// not lead the name remain unescaped, and a `/` becomes a `-`.
+fn escape_instance(name: &str) -> String {
+    name.bytes()
+        .enumerate()
+        .map(|(index, byte)| match byte {
+            b'/' => String::from("-"),
+            b'.' if index > 0 => String::from("."),
+            b':' | b'_' => (byte as char).to_string(),
+            _ if byte.is_ascii_alphanumeric() => (byte as char).to_string(),
+            _ => format!("\\x{:02x}", byte),
+        })
+        .collect()
+}
+
+/// Asks the machine's init system to shut the machine down cleanly.
+pub fn poweroff_machine(name: &str) -> Result<(), Error> {
+    let arguments = vec![name.into(), "leader".into(), MessageItem::Int32(POWEROFF_SIGNAL)];
+    call_machined("KillMachine", arguments).map(|_| ())
+}
+
+/// Kills every process of the machine immediately.
+pub fn terminate_machine(name: &str) -> Result<(), Error> {
+    call_machined("TerminateMachine", vec![name.into()]).map(|_| ())
+}
+
+/// Takes the `a(ssso)` reply of `ListMachines` and collects the name, class, service, and object path of each machine.
+fn parse_machines(items: &[MessageItem]) -> Vec<(String, String, String, String)> {
+    let machines = match items.get(0) {
+        Some(&MessageItem::Array(ref machines, _)) => machines,
+        _ => return Vec::new(),
+    };
+
+    machines.iter()
+        .filter_map(|machine| match *machine {
+            MessageItem::Struct(ref fields) => match (fields.get(0), fields.get(1), fields.get(2), fields.get(3)) {
+                (Some(&MessageItem::Str(ref name)), Some(&MessageItem::Str(ref class)),
+                    Some(&MessageItem::Str(ref service)), Some(&MessageItem::ObjectPath(ref path))) => {
+                    Some((name.clone(), class.clone(), service.clone(), path.to_string()))
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Takes the `a(ssbttto)` reply of `ListImages` and collects the name of each image. Hidden images, such as the
+/// `.host` image of the host's own root directory, are skipped.
+fn parse_images(items: &[MessageItem]) -> Vec<String> {
+    let images = match items.get(0) {
+        Some(&MessageItem::Array(ref images, _)) => images,
+        _ => return Vec::new(),
+    };
+
+    images.iter()
+        .filter_map(|image| match *image {
+            MessageItem::Struct(ref fields) => match fields.get(0) {
+                Some(&MessageItem::Str(ref name)) if !name.starts_with('.') => Some(name.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// The system manager inside a running container, as managed by `systemctl --machine`. The manager is reached
+/// through the system bus socket within the container's root directory, and its unit files and journal are read
+/// from the host, which requires root.
+#[derive(Clone, Debug)]
+pub struct MachineBackend {
+    machine: String,
+    leader: u32,
+    bus: AddressBackend,
+}
+
+impl MachineBackend {
+    /// Manages the units of the machine, which must be running.
+    pub fn new(machine: &Machine) -> Result<MachineBackend, Error> {
+        let leader = machine.leader.ok_or_else(|| Error::JobFailed(format!("{} is not running", machine.name)))?;
+        let address = format!("unix:path=/proc/{}/root/run/dbus/system_bus_socket", leader);
+        Ok(MachineBackend {
+            machine: machine.name.clone(),
+            leader: leader,
+            bus: AddressBackend::new(&address, BusType::System),
+        })
+    }
+
+    /// The root directory of the container, as seen through its init process, beneath which its files are found.
+    fn root(&self) -> String { format!("/proc/{}/root", self.leader) }
+}
+
+impl SystemdBackend for MachineBackend {
+    fn name(&self) -> String { format!("machine {}", self.machine) }
+
+    /// The units that systemd has not loaded are described by their unit files, beneath the container's root.
+    fn list_unit_files(&self) -> Result<Vec<SystemdUnit>, Error> {
+        let mut units = self.bus.list_unit_files()?;
+        dbus::describe_unloaded_units(&mut units, &self.root());
+        Ok(units)
+    }
+
+    fn reload_unit(&self, unit: &SystemdUnit) -> Result<SystemdUnit, Error> { self.bus.reload_unit(unit) }
+
+    fn unit_properties(&self, unit: &SystemdUnit, interface: &str) -> Result<BTreeMap<String, MessageItem>, Error> {
+        self.bus.unit_properties(unit, interface)
+    }
+
+    fn enable(&self, unit: &SystemdUnit) -> Result<bool, Error> { self.bus.enable(unit) }
+
+    fn disable(&self, unit: &SystemdUnit) -> Result<bool, Error> { self.bus.disable(unit) }
+
+    fn start(&self, unit: &SystemdUnit) -> Result<(), Error> { self.bus.start(unit) }
+
+    fn stop(&self, unit: &SystemdUnit) -> Result<(), Error> { self.bus.stop(unit) }
+
+    fn restart(&self, unit: &SystemdUnit) -> Result<(), Error> { self.bus.restart(unit) }
+
+    fn reload(&self, unit: &SystemdUnit) -> Result<(), Error> { self.bus.reload(unit) }
+
+    fn mask(&self, unit: &SystemdUnit) -> Result<bool, Error> { self.bus.mask(unit) }
+
+    /// The unit file is read through the root directory of the container's init process.
+    fn unit_file(&self, unit: &SystemdUnit) -> Result<String, Error> {
+        fs::read_to_string(format!("{}{}", self.root(), unit.path)).map_err(Error::from)
+    }
+
+    /// The unit file is written through the root directory of the container's init process, rather than to the
+    /// file of the same path on the host.
+    fn save_unit_file(&self, unit: &SystemdUnit, contents: &str) -> Result<(), Error> {
+        let mut file = OpenOptions::new().write(true).truncate(true).open(format!("{}{}", self.root(), unit.path))?;
+        file.write_all(contents.as_bytes()).map_err(Error::from)
+    }
+
+    fn dependencies(&self, unit: &SystemdUnit) -> Result<String, Error> { self.bus.dependencies(unit) }
+
+    fn journal(&self, query: &JournalQuery) -> Result<String, Error> { self.scope_journal(query)?.read() }
+
+    fn scope_journal(&self, query: &JournalQuery) -> Result<JournalQuery, Error> {
+        let mut query = query.clone();
+        query.machine = Some(self.machine.clone());
+        Ok(query)
+    }
+
+    fn blame(&self) -> Result<Vec<Analyze>, Error> { self.bus.blame() }
+
+    fn boot_time(&self) -> Result<(String, String, String), Error> { self.bus.boot_time() }
+
+    fn watch(&self, changed: Sender<(BusType, String)>, stop: &AtomicBool) -> Result<(), Error> {
+        self.bus.watch(changed, stop)
+    }
+}
+
+#[test]
+fn test_parse_machines() {
+    let machine = |name: &str, class: &str| MessageItem::Struct(vec![
+        MessageItem::Str(name.to_owned()),
+        MessageItem::Str(class.to_owned()),
+        MessageItem::Str(String::from("systemd-nspawn")),
+        MessageItem::ObjectPath(format!("/org/freedesktop/machine1/machine/{}", name).into()),
+    ]);
+    let reply = [MessageItem::Array(vec![machine("web", "container"), machine("db", "vm")], "(ssso)".into())];
+    assert_eq!(parse_machines(&reply), vec![
+        (String::from("web"), String::from("container"), String::from("systemd-nspawn"),
+            String::from("/org/freedesktop/machine1/machine/web")),
+        (String::from("db"), String::from("vm"), String::from("systemd-nspawn"),
+            String::from("/org/freedesktop/machine1/machine/db")),
+    ]);
+}
+
+#[test]
+fn test_parse_images() {
+    let image = |name: &str| MessageItem::Struct(vec![
+        MessageItem::Str(name.to_owned()),
+        MessageItem::Str(String::from("directory")),
+        MessageItem::Bool(false),
+        MessageItem::UInt64(0),
+        MessageItem::UInt64(0),
+        MessageItem::UInt64(0),
+        MessageItem::ObjectPath("/org/freedesktop/machine1/image/web".into()),
+    ]);
+    let reply = [MessageItem::Array(vec![image(".host"), image("web")], "(ssbttto)".into())];
+    assert_eq!(parse_images(&reply), vec![String::from("web")]);
+}
+
+#[test]
+fn test_escape_instance() {
+    assert_eq!(escape_instance("web"), "web");
+    assert_eq!(escape_instance("my-container"), "my\\x2dcontainer");
+    assert_eq!(escape_instance(".hidden/root"), "\\x2ehidden-root");
+    assert_eq!(escape_instance("db_1.local"), "db_1.local");
+}
+
+#[test]
+fn test_machine_unit_file() {
+    use std::process;
+    use super::{ActiveState, UnitState};
+
+    // The root of the test's own process is that of the host, so the file is written through it like a container's.
+    let machine = Machine {
+        name: String::from("web"),
+        class: String::from("container"),
+        service: String::from("systemd-nspawn"),
+        state: String::from("running"),
+        leader: Some(process::id()),
+    };
+    let backend = MachineBackend::new(&machine).unwrap();
+    assert_eq!(backend.root(), format!("/proc/{}/root", process::id()));
+
+    let path = std::env::temp_dir().join(format!("systemd-manager-machine-{}.service", process::id()));
+    fs::write(&path, "[Unit]\n").unwrap();
+    let unit = SystemdUnit {
+        path: path.to_str().unwrap().to_owned(),
+        ..SystemdUnit::loaded("nginx.service", "Web Server", UnitState::Enabled, ActiveState::Active)
+    };
+    assert_eq!(backend.save_unit_file(&unit, "[Unit]\nDescription=Web Server\n"), Ok(()));
+    assert_eq!(backend.unit_file(&unit), Ok(String::from("[Unit]\nDescription=Web Server\n")));
+    fs::remove_file(&path).unwrap();
+
+    let mut stopped = machine.clone();
+    stopped.leader = None;
+    assert!(MachineBackend::new(&stopped).is_err());
+}
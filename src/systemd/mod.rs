@@ -1,114 +1,267 @@
+//! The systemd backend, which manages units through systemd's D-Bus API and reads from the commands that systemd
+//! provides, such as `systemctl`, `systemd-analyze`, and `journalctl`.
+
 pub mod analyze;
+pub mod backend;
+mod connection;
 pub mod dbus;
+pub mod diagnostics;
+mod error;
+pub mod journal;
+pub mod logind;
+pub mod machined;
+pub mod resources;
 pub mod systemctl;
 
-use std::fs::File;
-use std::io::Read;
+pub use self::error::Error;
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
 use std::path::Path;
-use std::process::Command;
 use self::dbus::dbus::BusType as BusType;
+use self::journal::JournalQuery;
 
-
+/// A unit file that is known to a systemd manager, along with its enablement and runtime state.
 #[derive(Clone, Debug)]
 pub struct SystemdUnit {
+    /// The file name of the unit, such as `sshd.service`.
     pub name: String,
+    /// The path of the unit file.
     pub path: String,
+    /// The description of the unit, which is read from its unit file if systemd has not loaded the unit.
+    pub description: String,
+    /// Whether systemd loaded the unit file, such as `loaded` or `not-found`, which is empty if it is not loaded.
+    pub load_state: String,
+    /// The enablement state of the unit file.
     pub state: UnitState,
+    /// Whether the unit is currently running.
+    pub active_state: ActiveState,
+    /// The type-specific state of the unit, such as `running` or `dead`.
+    pub sub_state: String,
+    /// The type of the unit, determined by its extension.
     pub utype: UnitType,
+    /// The bus of the manager that the unit belongs to: the system bus, or the user's session bus.
     pub bustype: BusType,
+    /// The time that the unit last changed its state, in microseconds since the Unix epoch, once it has been reloaded.
+    pub state_change: Option<u64>,
+    /// Whether the unit is able to reload its configuration, which is unknown until the unit has been reloaded.
+    pub can_reload: Option<bool>,
 }
 
 impl SystemdUnit {
     /// Read the unit file and return it's contents so that we can display it in the `gtk::TextView`.
-    pub fn get_info(&self) -> String {
-        File::open(&self.path)
-            .ok()
-            // Take the contained file and return a `String` of the file contents, else return an empty `String`.
-            .map_or(String::new(), |mut file| {
-                // Obtain the capacity to create the string with based on the file's metadata.
-                let capacity = file.metadata().map(|x| x.len()).unwrap_or(0) as usize;
-                // Create a `String` to store the contents of the file.
-                let mut output = String::with_capacity(capacity);
-                // Attempt to read the file to the `String`, or return an empty `String` if it fails.
-                file.read_to_string(&mut output)
-                    .map(|_| output)
-                    .ok()
-                    .unwrap_or_default()
-            })
+    pub fn get_info(&self) -> Result<String, Error> {
+        let mut file = File::open(&self.path)?;
+        // Obtain the capacity to create the string with based on the file's metadata.
+        let capacity = file.metadata().map(|x| x.len()).unwrap_or(0) as usize;
+        // Create a `String` to store the contents of the file, and read the file to the `String`.
+        let mut output = String::with_capacity(capacity);
+        file.read_to_string(&mut output)?;
+        Ok(output)
+    }
+
+    /// Replaces the contents of the unit file with the given contents.
+    pub fn save_info(&self, contents: &str) -> Result<(), Error> {
+        // Open the unit file with write access, truncating the previous contents.
+        let mut file = OpenOptions::new().write(true).truncate(true).open(&self.path)?;
+        file.write_all(contents.as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the query used to read the current boot's journal entries for the given unit.
+    pub fn journal_query(&self) -> JournalQuery {
+        JournalQuery::unit(&self.name, self.bustype)
     }
 
     /// Obtains the journal log for the given unit.
-    pub fn get_journal(&self) -> String {
-        Command::new("journalctl")
-            .arg(match self.bustype {
-                BusType::Session => "--user",
-                _ => ""
-            })
-            .arg("-b")
-            .arg("-r")
-            .arg("-u")
-            .arg(&self.name)
-            .output()
-            .ok()
-            // Collect the output of the journal as a `String`
-            .and_then(|output| String::from_utf8(output.stdout).ok())
-            // Return the contents of the journal, otherwise return an error message
-            .unwrap_or_else(|| format!("Unable to read the journal entry for {}.", self.name))
+    pub fn get_journal(&self) -> Result<String, Error> {
+        self.journal_query().read()
+    }
+
+    /// Names the manager that the unit belongs to, which is either `system` or `user`.
+    pub fn bus_label(&self) -> &'static str { bus_label(self.bustype) }
+
+    /// Identifies the unit among the units of both managers, as a unit of the same name, such as `dbus.socket`, may
+    /// belong to both the system's and the user's manager.
+    pub fn key(&self) -> String {
+        unit_key(self.bustype, &self.name)
+    }
+
+    /// Reports a unit that entered the failed state once its start job had run, which systemd does not report as an
+    /// error of the method that queued the job.
+    pub fn check_started(&self) -> Result<(), Error> {
+        if self.active_state == ActiveState::Failed {
+            Err(Error::JobFailed(format!("{} entered the failed state ({})", self.name, self.sub_state)))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Reports a unit that is still running once its stop job had run.
+    pub fn check_stopped(&self) -> Result<(), Error> {
+        match self.active_state {
+            ActiveState::Active | ActiveState::Reloading => {
+                Err(Error::JobFailed(format!("{} is still running ({})", self.name, self.sub_state)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// A loaded unit of the system's manager, whose unit file is in `/usr/lib/systemd/system`, for the tests to modify.
+    #[cfg(test)]
+    pub fn loaded(name: &str, description: &str, state: UnitState, active_state: ActiveState) -> SystemdUnit {
+        let sub_state = match active_state {
+            ActiveState::Active => "running",
+            ActiveState::Failed => "failed",
+            _ => "dead",
+        };
+        SystemdUnit {
+            name: name.to_owned(),
+            path: format!("/usr/lib/systemd/system/{}", name),
+            description: description.to_owned(),
+            load_state: String::from("loaded"),
+            state: state,
+            active_state: active_state,
+            sub_state: String::from(sub_state),
+            utype: UnitType::from_name(name).unwrap(),
+            bustype: BusType::System,
+            state_change: None,
+            can_reload: None,
+        }
     }
 }
 
+/// The key of the unit with the given name that belongs to the manager of the given bus, as `SystemdUnit::key`.
+pub fn unit_key(bustype: BusType, name: &str) -> String {
+    format!("{}/{}", bus_label(bustype), name)
+}
+
+/// Names the manager of the given bus, which is either `system` or `user`.
+fn bus_label(bustype: BusType) -> &'static str {
+    if bustype == BusType::Session { "user" } else { "system" }
+}
+
+/// The type of a unit, which is determined by the extension of its unit file.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum UnitType {
+    /// An automount point, ending with `.automount`.
     Automount,
+    /// A bus name, ending with `.busname`.
     Busname,
+    /// A mount point, ending with `.mount`.
     Mount,
+    /// A path that is monitored, ending with `.path`.
     Path,
+    /// A group of externally-created processes, ending with `.scope`.
     Scope,
+    /// A service, ending with `.service`.
     Service,
+    /// A group of units in the resource control tree, ending with `.slice`.
     Slice,
+    /// A socket for socket activation, ending with `.socket`.
     Socket,
+    /// A swap device or file, ending with `.swap`.
     Swap,
+    /// A group of units used as a synchronization point, ending with `.target`.
     Target,
+    /// A timer for timer-based activation, ending with `.timer`.
     Timer,
 }
 impl UnitType {
     /// Takes the pathname of the unit as input to determine what type of unit it is.
     pub fn new(pathname: &str) -> UnitType {
-        match Path::new(pathname).extension().unwrap().to_str().unwrap() {
-            "automount" => UnitType::Automount,
-            "busname" => UnitType::Busname,
-            "mount" => UnitType::Mount,
-            "path" => UnitType::Path,
-            "scope" => UnitType::Scope,
-            "service" => UnitType::Service,
-            "slice" => UnitType::Slice,
-            "socket" => UnitType::Socket,
-            "swap" => UnitType::Swap,
-            "target" => UnitType::Target,
-            "timer" => UnitType::Timer,
-            _ => panic!("Unknown Type: {}", pathname),
+        UnitType::from_name(pathname).unwrap_or_else(|| panic!("Unknown Type: {}", pathname))
+    }
+
+    /// Determines the type of the unit from the extension of its name or path, returning `None` if the extension is
+    /// missing or is not that of a unit.
+    pub fn from_name(name: &str) -> Option<UnitType> {
+        match Path::new(name).extension().and_then(|extension| extension.to_str()).unwrap_or("") {
+            "automount" => Some(UnitType::Automount),
+            "busname" => Some(UnitType::Busname),
+            "mount" => Some(UnitType::Mount),
+            "path" => Some(UnitType::Path),
+            "scope" => Some(UnitType::Scope),
+            "service" => Some(UnitType::Service),
+            "slice" => Some(UnitType::Slice),
+            "socket" => Some(UnitType::Socket),
+            "swap" => Some(UnitType::Swap),
+            "target" => Some(UnitType::Target),
+            "timer" => Some(UnitType::Timer),
+            _ => None,
+        }
+    }
+
+    /// The D-Bus interface that holds the properties which are specific to this type of unit.
+    pub fn interface(&self) -> &'static str {
+        match *self {
+            UnitType::Automount => "org.freedesktop.systemd1.Automount",
+            UnitType::Busname => "org.freedesktop.systemd1.BusName",
+            UnitType::Mount => "org.freedesktop.systemd1.Mount",
+            UnitType::Path => "org.freedesktop.systemd1.Path",
+            UnitType::Scope => "org.freedesktop.systemd1.Scope",
+            UnitType::Service => "org.freedesktop.systemd1.Service",
+            UnitType::Slice => "org.freedesktop.systemd1.Slice",
+            UnitType::Socket => "org.freedesktop.systemd1.Socket",
+            UnitType::Swap => "org.freedesktop.systemd1.Swap",
+            UnitType::Target => "org.freedesktop.systemd1.Target",
+            UnitType::Timer => "org.freedesktop.systemd1.Timer",
+        }
+    }
+
+    /// The name of the type as it appears in the suffix of unit names, such as `service`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            UnitType::Automount => "automount",
+            UnitType::Busname => "busname",
+            UnitType::Mount => "mount",
+            UnitType::Path => "path",
+            UnitType::Scope => "scope",
+            UnitType::Service => "service",
+            UnitType::Slice => "slice",
+            UnitType::Socket => "socket",
+            UnitType::Swap => "swap",
+            UnitType::Target => "target",
+            UnitType::Timer => "timer",
         }
     }
 }
 
+/// The enablement state of a unit file, as reported by the `UnitFileState` property.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum UnitState {
+    /// The unit file is invalid.
     Bad,
+    /// The unit file is not enabled.
     Disabled,
+    /// The unit file is enabled, and will be started when its target is reached.
     Enabled,
+    /// The unit file was generated dynamically by a generator.
     Generated,
+    /// The unit file is enabled indirectly, through the units listed in its `Also=` setting.
     Indirect,
+    /// The unit file is made available through a symlink from outside of the unit search path.
     Linked,
+    /// The unit file is linked to `/dev/null`, so that it cannot be started.
     Masked,
+    /// The unit file has no `[Install]` section, so it cannot be enabled.
     Static,
+    /// The unit was created dynamically at runtime.
     Transient,
+    /// The unit file is an alias of another unit file.
     Alias,
 }
 impl UnitState {
     /// Takes the string containing the state information from the dbus message and converts it
     /// into a UnitType by matching the first character.
     pub fn new(x: &str) -> UnitState {
-        match x.chars().skip(6).take_while(|x| *x != '\"').next().unwrap() {
+        UnitState::from_name(&x.chars().skip(6).take_while(|x| *x != '\"').collect::<String>())
+    }
+
+    /// Takes the name of the state, such as `enabled`, and converts it into a UnitState by matching the first
+    /// character. States that are unknown, or missing, are considered to be bad.
+    pub fn from_name(x: &str) -> UnitState {
+        match x.chars().next().unwrap_or(' ') {
             's' => UnitState::Static,
             'd' => UnitState::Disabled,
             'e' => UnitState::Enabled,
@@ -119,7 +272,66 @@ impl UnitState {
             'g' => UnitState::Generated,
             't' => UnitState::Transient,
             'a' => UnitState::Alias,
-            _ => panic!("Unknown State: {}", x),
+            _ => UnitState::Bad,
+        }
+    }
+
+    /// The name of the state as systemd writes it in the `UnitFileState` property, such as `enabled`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            UnitState::Bad => "bad",
+            UnitState::Disabled => "disabled",
+            UnitState::Enabled => "enabled",
+            UnitState::Generated => "generated",
+            UnitState::Indirect => "indirect",
+            UnitState::Linked => "linked",
+            UnitState::Masked => "masked",
+            UnitState::Static => "static",
+            UnitState::Transient => "transient",
+            UnitState::Alias => "alias",
+        }
+    }
+}
+
+/// The runtime state of a unit, as reported by the `ActiveState` property.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ActiveState {
+    /// The unit is running.
+    Active,
+    /// The unit is running, and is reloading its configuration.
+    Reloading,
+    /// The unit is not running.
+    Inactive,
+    /// The unit is not running, as it failed.
+    Failed,
+    /// The unit is starting.
+    Activating,
+    /// The unit is stopping.
+    Deactivating,
+}
+impl ActiveState {
+    /// Takes the value of the `ActiveState` property and converts it into an `ActiveState`. Units that systemd
+    /// has not loaded have no active state, and are therefore considered to be inactive.
+    pub fn new(x: &str) -> ActiveState {
+        match x {
+            "active" => ActiveState::Active,
+            "reloading" => ActiveState::Reloading,
+            "failed" => ActiveState::Failed,
+            "activating" => ActiveState::Activating,
+            "deactivating" => ActiveState::Deactivating,
+            _ => ActiveState::Inactive,
+        }
+    }
+
+    /// The name of the state as systemd writes it in the `ActiveState` property, such as `active`.
+    pub fn as_str(&self) -> &'static str {
+        match *self {
+            ActiveState::Active => "active",
+            ActiveState::Reloading => "reloading",
+            ActiveState::Inactive => "inactive",
+            ActiveState::Failed => "failed",
+            ActiveState::Activating => "activating",
+            ActiveState::Deactivating => "deactivating",
         }
     }
 }
@@ -133,9 +345,11 @@ pub fn get_unit_description(info: &str) -> Option<&str> {
         .map(|description| description.split_at(12).1)
 }
 
-/// Returns true if the given `UnitType` and `UnitState` indicates that the unit can be toggled.
+/// Returns true if the given `UnitType` and `UnitState` indicates that the unit can be toggled. Masked units are
+/// included, as they are disabled units that can be found through the unit filters.
 fn is_togglable(utype: &UnitType, ustate: &UnitState, wanted_type: &UnitType) -> bool {
-    utype == wanted_type && (ustate == &UnitState::Enabled || ustate == &UnitState::Disabled)
+    utype == wanted_type && (ustate == &UnitState::Enabled || ustate == &UnitState::Disabled
+        || ustate == &UnitState::Masked)
 }
 
 /// Takes a `Vec<SystemdUnit>` as input and returns a new vector only containing services which can be enabled and
@@ -182,6 +396,43 @@ fn test_get_unit_description() {
     assert_eq!(get_unit_description(input), None);
 }
 
+#[test]
+fn test_unit_key() {
+    let mut unit = SystemdUnit::loaded("dbus.socket", "D-Bus System Message Bus Socket", UnitState::Static,
+        ActiveState::Active);
+    assert_eq!(unit.key(), "system/dbus.socket");
+    unit.bustype = BusType::Session;
+    assert_eq!(unit.key(), "user/dbus.socket");
+}
+
+#[test]
+fn test_unit_type() {
+    assert_eq!(UnitType::from_name("getty@tty1.service"), Some(UnitType::Service));
+    assert_eq!(UnitType::from_name("/usr/lib/systemd/system/fstrim.timer"), Some(UnitType::Timer));
+    assert_eq!(UnitType::from_name("sshd"), None);
+    assert_eq!(UnitType::from_name("notes.txt"), None);
+    assert_eq!(UnitType::from_name("fstrim.timer").map(|utype| utype.as_str()), Some("timer"));
+}
+
+#[test]
+fn test_unit_state() {
+    assert_eq!(UnitState::new(" Str(\"enabled\")"), UnitState::Enabled);
+    assert_eq!(UnitState::from_name("masked"), UnitState::Masked);
+    assert_eq!(UnitState::from_name("disabled"), UnitState::Disabled);
+    assert_eq!(UnitState::from_name("frobnicated"), UnitState::Bad);
+    assert_eq!(UnitState::from_name(""), UnitState::Bad);
+    assert_eq!(UnitState::from_name(UnitState::Indirect.as_str()), UnitState::Indirect);
+}
+
+#[test]
+fn test_active_state() {
+    assert_eq!(ActiveState::new("active"), ActiveState::Active);
+    assert_eq!(ActiveState::new("failed"), ActiveState::Failed);
+    assert_eq!(ActiveState::new("deactivating"), ActiveState::Deactivating);
+    assert_eq!(ActiveState::new(""), ActiveState::Inactive);
+    assert_eq!(ActiveState::new(ActiveState::Deactivating.as_str()), ActiveState::Deactivating);
+}
+
 #[test]
 fn test_is_togglable() {
     let static_state = &UnitState::Static;
@@ -206,4 +457,8 @@ fn test_is_togglable() {
         is_togglable(timer_type, disabled_state, &UnitType::Timer),
         true
     );
+    assert_eq!(
+        is_togglable(service_type, &UnitState::Masked, &UnitType::Service),
+        true
+    );
 }
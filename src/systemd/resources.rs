@@ -0,0 +1,140 @@
+//! Collects when a unit last changed its state and the resources that it is using, from the properties that systemd
+//! accounts for each unit.
+
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use super::backend::SystemdBackend;
+use super::dbus::dbus::MessageItem;
+use super::{SystemdUnit, UnitType};
+
+/// systemd reports accounting values that are not available, such as the memory of a unit without a control group,
+/// as the largest possible value.
+const UNSET: u64 = ::std::u64::MAX;
+
+/// Requesting the properties of an empty interface name obtains the properties of every interface of the unit.
+const ALL_INTERFACES: &str = "";
+
+/// When a unit last changed its state, and the resources that its processes are using.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct UnitResources {
+    /// The time that the unit last changed its state, in microseconds since the Unix epoch.
+    pub state_change: Option<u64>,
+    /// The memory that is used by the unit's processes, in bytes.
+    pub memory: Option<u64>,
+    /// The CPU time that was consumed by the unit's processes, in nanoseconds.
+    pub cpu: Option<u64>,
+}
+
+impl UnitResources {
+    /// Collects the resources of the unit through the backend, with a single request for the unit's properties.
+    /// Units that are not loaded are not collected, as obtaining their properties would load them.
+    pub fn collect(backend: &dyn SystemdBackend, unit: &SystemdUnit) -> UnitResources {
+        if unit.load_state.is_empty() {
+            return UnitResources::default();
+        }
+
+        // The properties of every interface are requested at once for units whose resources are accounted for, as
+        // the time of the last state change belongs to the unit's interface rather than to the type's interface.
+        let interface = if is_accounted(&unit.utype) { ALL_INTERFACES } else { "org.freedesktop.systemd1.Unit" };
+        UnitResources::from_properties(&backend.unit_properties(unit, interface).unwrap_or_default())
+    }
+
+    /// Takes the properties of the unit, which include those of the unit type's interface for accounted units.
+    fn from_properties(properties: &BTreeMap<String, MessageItem>) -> UnitResources {
+        UnitResources {
+            state_change: get_u64(properties, "StateChangeTimestamp").filter(|&time| time != 0),
+            memory: get_u64(properties, "MemoryCurrent"),
+            cpu: get_u64(properties, "CPUUsageNSec"),
+        }
+    }
+}
+
+/// Whether the resources of the unit type are accounted for, as only units that have a control group of their own
+/// are accounted for.
+fn is_accounted(utype: &UnitType) -> bool {
+    match *utype {
+        UnitType::Service | UnitType::Socket | UnitType::Mount | UnitType::Swap | UnitType::Slice | UnitType::Scope => {
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Obtains an unsigned 64-bit property, unless it is unset.
+fn get_u64(properties: &BTreeMap<String, MessageItem>, name: &str) -> Option<u64> {
+    match properties.get(name) {
+        Some(&MessageItem::UInt64(value)) if value != UNSET => Some(value),
+        _ => None,
+    }
+}
+
+/// Formats a number of bytes with a binary unit, such as `12.5 MiB`.
+pub fn format_bytes(bytes: u64) -> String {
+    let units = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < units.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 { format!("{} B", bytes) } else { format!("{:.1} {}", value, units[unit]) }
+}
+
+/// Formats a CPU time in nanoseconds, such as `1.25s`, or `2min 5s` once it exceeds a minute.
+pub fn format_cpu(nanoseconds: u64) -> String {
+    let seconds = nanoseconds / 1_000_000_000;
+    if seconds >= 60 {
+        format_duration(seconds)
+    } else {
+        format!("{:.2}s", nanoseconds as f64 / 1_000_000_000.0)
+    }
+}
+
+/// Formats how long ago a timestamp in microseconds since the Unix epoch was, such as `5min ago`.
+pub fn format_since(timestamp: u64) -> String {
+    format!("{} ago", format_elapsed(timestamp))
+}
+
+/// Formats how much time has passed since a timestamp in microseconds since the Unix epoch, such as `5min 10s`.
+pub fn format_elapsed(timestamp: u64) -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or(0);
+    format_duration(now.saturating_sub(timestamp / 1_000_000))
+}
+
+/// Formats a number of seconds with its two most significant units, such as `2h 5min`.
+fn format_duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86_400, seconds % 86_400 / 3600, seconds % 3600 / 60);
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}min", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}min {}s", minutes, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+#[test]
+fn test_from_properties() {
+    let mut properties = BTreeMap::new();
+    properties.insert(String::from("StateChangeTimestamp"), MessageItem::UInt64(1_500_000_000_000_000));
+    properties.insert(String::from("MemoryCurrent"), MessageItem::UInt64(4096));
+    properties.insert(String::from("CPUUsageNSec"), MessageItem::UInt64(UNSET));
+    assert_eq!(UnitResources::from_properties(&properties), UnitResources {
+        state_change: Some(1_500_000_000_000_000),
+        memory: Some(4096),
+        cpu: None,
+    });
+    assert_eq!(UnitResources::from_properties(&BTreeMap::new()), UnitResources::default());
+}
+
+#[test]
+fn test_format_resources() {
+    assert_eq!(format_bytes(512), "512 B");
+    assert_eq!(format_bytes(13_107_200), "12.5 MiB");
+    assert_eq!(format_cpu(1_250_000_000), "1.25s");
+    assert_eq!(format_cpu(125_000_000_000), "2min 5s");
+    assert_eq!(format_duration(7500), "2h 5min");
+    assert_eq!(format_duration(90_000), "1d 1h");
+}
@@ -1,57 +1,85 @@
-use std::process::Command;
-use super::SystemdUnit;
+//! Obtains information about units that is only available through `systemctl`.
+
+use std::process::{Command, Output};
+use super::{Error, SystemdUnit};
 use super::dbus::dbus::BusType as BusType;
 
+/// The information about a unit that is obtained by running `systemctl`.
 pub trait Systemctl {
     /// Runs the `systemctl status` command and receives it's stdout to determin the active status of the unit.
-    fn is_active(&self) -> bool;
+    fn is_active(&self) -> Result<bool, Error>;
 
     /// Runs `systemctl list-dependencies` to obtain a list of dependencies for the given unit.
-    fn list_dependencies(&self) -> String;
+    fn list_dependencies(&self) -> Result<String, Error>;
 }
 
 impl Systemctl for SystemdUnit {
-    fn is_active(&self) -> bool {
-        Command::new("systemctl").arg(match self.bustype {
-            BusType::Session => "--user",
-            _ => ""
-        }).arg("status").arg(&self.name).output().ok()
-            // Collect the command's standard output as a `String` and return it as an `Option`.
-            .and_then(|output| String::from_utf8(output.stdout).ok())
-            // Determine whether the state of the input is active or not.
-            .map_or(false, |stdout| parse_state(stdout.as_str()))
+    fn is_active(&self) -> Result<bool, Error> {
+        let output = systemctl(self.bustype).arg("status").arg(&self.name).output()?;
+        // `systemctl status` exits with a non-zero status for inactive units, so the output is parsed first.
+        stdout(&output).and_then(|stdout| parse_state(stdout.as_str())
+            .ok_or_else(|| failure(&output, &self.name)))
+    }
+
+    fn list_dependencies(&self) -> Result<String, Error> {
+        let output = systemctl(self.bustype).arg("list-dependencies").arg(&self.name).output()?;
+        if !output.status.success() {
+            return Err(failure(&output, &self.name));
+        }
+
+        // Skip the first line of the output
+        Ok(stdout(&output)?.lines().skip(1)
+            // Skip the first four characters of each line
+            .map(|x| x.chars().skip(4).collect::<String>())
+            // Fold each line into a single `String`.
+            .fold(String::new(), |acc, x| acc + x.as_str() + "\n"))
     }
+}
+
+/// Creates a `systemctl` command that operates upon the manager of the given bus.
+fn systemctl(bustype: BusType) -> Command {
+    let mut command = Command::new("systemctl");
+    if let BusType::Session = bustype {
+        command.arg("--user");
+    }
+    command
+}
 
-    fn list_dependencies(&self) -> String {
-        Command::new("systemctl").arg(match self.bustype {
-            BusType::Session => "--user",
-            _ => ""
-        }).arg("list-dependencies").arg(&self.name).output().ok()
-            // Collect the command's standard output as a `String` and return it as an `Option`.
-            .and_then(|output| String::from_utf8(output.stdout).ok())
-            // Collect a list of dependencies as a `String`, else return the unit's name.
-            .map_or(self.name.clone(), |stdout| {
-                // Skip the first line of the output
-                stdout.lines().skip(1)
-                    // Skip the first four characters of each line
-                    .map(|x| x.chars().skip(4).collect::<String>())
-                    // Fold each line into a single `String`.
-                    .fold(String::new(), |acc, x| acc + x.as_str() + "\n")
-            })
+/// Collects the standard output of the command as a `String`.
+fn stdout(output: &Output) -> Result<String, Error> {
+    String::from_utf8(output.stdout.clone()).map_err(|why| Error::Parse(why.to_string()))
+}
+
+/// Determines why `systemctl` failed from its standard error.
+fn failure(output: &Output, name: &str) -> Error {
+    let stderr = String::from_utf8_lossy(&output.stderr).trim().to_owned();
+    if stderr.contains("could not be found") || stderr.contains("not found") {
+        Error::NoSuchUnit(name.to_owned())
+    } else if stderr.contains("Access denied") || stderr.contains("Permission denied") {
+        Error::AccessDenied(stderr)
+    } else if stderr.contains("Failed to connect to bus") {
+        Error::BusUnavailable(stderr)
+    } else if stderr.is_empty() {
+        Error::Parse(format!("unexpected output from systemctl for {}", name))
+    } else {
+        Error::JobFailed(stderr)
     }
 }
 
 /// Parses the stdout of `systemctl status` to determine if the unit is active (true) or inactive (false).
-fn parse_state(status: &str) -> bool {
-    // The second line contains information pertaining to the state.
-    status.lines().nth(2)
-        // Determines whether the unit is status is active or inactive.
-        .map_or_else(|| false, |active_line| {
-            // Collect the first letter from the status parameter which is either '[a]ctive' or '[i]nactive'
-            active_line.trim().split_at(8).1.chars().next()
-                // If the character is `a` then the status is `[a]ctive`.
-                .map_or(false, |value| value == 'a')
-        })
+/// Returns `None` if the output does not contain the state of the unit.
+fn parse_state(status: &str) -> Option<bool> {
+    // The state is on the line that begins with `Active: `, which may be preceded by lines such as `Drop-In:` or
+    // `TriggeredBy:`, so its position varies.
+    status.lines()
+        .map(str::trim)
+        .find(|line| line.starts_with("Active: "))
+        // The state follows the `Active: ` label.
+        .map(|active_line| active_line.split_at(8).1)
+        // Collect the first letter from the state, which is either '[a]ctive' or '[i]nactive'
+        .and_then(|state| state.chars().next())
+        // If the character is `a` then the status is `[a]ctive`.
+        .map(|value| value == 'a')
 }
 
 #[test]
@@ -59,7 +87,7 @@ fn test_parse_state_active() {
     let input = r##"● systemd-networkd.service - Network Service
    Loaded: loaded (/usr/lib/systemd/system/systemd-networkd.service; enabled; vendor preset: enabled)
    Active: active (running) since Wed 2016-05-18 14:13:36 EDT; 12h ago"##;
-   assert_eq!(parse_state(input), true);
+   assert_eq!(parse_state(input), Some(true));
 }
 
 #[test]
@@ -67,5 +95,21 @@ fn test_parse_state_inactive() {
     let input = r##"● NetworkManager.service - Network Manager
    Loaded: loaded (/usr/lib/systemd/system/NetworkManager.service; disabled; vendor preset: disabled)
    Active: inactive (dead)"##;
-   assert_eq!(parse_state(input), false);
+   assert_eq!(parse_state(input), Some(false));
+}
+
+#[test]
+fn test_parse_state_after_drop_ins() {
+    let input = r##"● cups.service - CUPS Scheduler
+     Loaded: loaded (/usr/lib/systemd/system/cups.service; enabled; vendor preset: disabled)
+    Drop-In: /etc/systemd/system/cups.service.d
+             └─override.conf
+TriggeredBy: ● cups.socket
+     Active: inactive (dead) since Thu 2016-05-19 09:02:11 EDT; 3min ago"##;
+   assert_eq!(parse_state(input), Some(false));
+}
+
+#[test]
+fn test_parse_state_missing() {
+    assert_eq!(parse_state("Unit example.service could not be found."), None);
 }
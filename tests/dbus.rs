@@ -0,0 +1,200 @@
+//! Exercises the D-Bus backend against the fake systemd manager of the `mock` module. Each test is skipped when
+//! `dbus-daemon` is not installed.
+
+extern crate systemd_manager;
+
+mod mock;
+
+use std::env;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use mock::MockUnit;
+use systemd_manager::{ActiveState, AddressBackend, BusType, Dbus, Error, LocalBackend, SystemdBackend, UnitState,
+    UnitType};
+use systemd_manager::systemd::dbus::{find_unit, list_unit_files, list_units, load_unit, reload_unit};
+
+/// Obtains the fake manager, or skips the test if it could not be launched.
+macro_rules! mock_systemd {
+    () => {
+        match mock::systemd() {
+            Some(systemd) => systemd,
+            None => return,
+        }
+    }
+}
+
+#[test]
+fn test_list_unit_files() {
+    let systemd = mock_systemd!();
+    systemd.add_unit(MockUnit::new("list-sshd.service", "OpenSSH Daemon").enabled().running());
+    systemd.add_unit(MockUnit::new("list-cups.socket", "CUPS Scheduler"));
+
+    let units = list_unit_files(BusType::Session).unwrap();
+    let sshd = units.iter().find(|unit| unit.name == "list-sshd.service").unwrap();
+    assert_eq!(sshd.path, "/etc/systemd/system/list-sshd.service");
+    assert_eq!(sshd.description, "OpenSSH Daemon");
+    assert_eq!(sshd.load_state, "loaded");
+    assert_eq!(sshd.state, UnitState::Enabled);
+    assert_eq!(sshd.active_state, ActiveState::Active);
+    assert_eq!(sshd.sub_state, "running");
+    assert_eq!(sshd.utype, UnitType::Service);
+
+    let cups = units.iter().find(|unit| unit.name == "list-cups.socket").unwrap();
+    assert_eq!(cups.state, UnitState::Disabled);
+    assert_eq!(cups.active_state, ActiveState::Inactive);
+    assert_eq!(cups.utype, UnitType::Socket);
+
+    // The units are sorted by their names.
+    let names: Vec<&str> = units.iter().map(|unit| unit.name.as_str()).collect();
+    let mut sorted = names.clone();
+    sorted.sort_by_key(|name| name.to_lowercase());
+    assert_eq!(names, sorted);
+
+    assert!(list_units(BusType::Session).unwrap().iter().any(|unit| unit.name == "list-sshd.service"));
+}
+
+#[test]
+fn test_find_unit() {
+    let systemd = mock_systemd!();
+    systemd.add_unit(MockUnit::new("find-avahi.service", "Avahi Daemon"));
+
+    assert_eq!(find_unit(BusType::Session, "find-avahi.service").unwrap().description, "Avahi Daemon");
+    assert_eq!(find_unit(BusType::Session, "find-missing.service").unwrap_err(),
+        Error::NoSuchUnit(String::from("find-missing.service")));
+}
+
+#[test]
+fn test_load_unit() {
+    let systemd = mock_systemd!();
+    systemd.add_unit(MockUnit::new("load-postgresql.service", "Database Server").running());
+
+    let unit = load_unit(BusType::Session, "load-postgresql.service").unwrap();
+    assert_eq!(unit.path, "/etc/systemd/system/load-postgresql.service");
+    assert_eq!(unit.description, "Database Server");
+    assert_eq!(unit.state, UnitState::Disabled);
+    assert_eq!(unit.active_state, ActiveState::Active);
+    match load_unit(BusType::Session, "load-missing.service") {
+        Err(Error::NoSuchUnit(_)) => (),
+        result => panic!("expected the unit to be missing, but found {:?}", result),
+    }
+    assert_eq!(load_unit(BusType::Session, "load-postgresql").unwrap_err(),
+        Error::NoSuchUnit(String::from("load-postgresql")));
+}
+
+#[test]
+fn test_enable_and_disable() {
+    let systemd = mock_systemd!();
+    systemd.add_unit(MockUnit::new("enable-cronie.service", "Command Scheduler"));
+    let unit = find_unit(BusType::Session, "enable-cronie.service").unwrap();
+
+    assert_eq!(unit.is_enabled(), Ok(false));
+    assert_eq!(unit.enable(), Ok(false));
+    assert_eq!(systemd.unit("enable-cronie.service").unwrap().file_state, "enabled");
+    assert_eq!(unit.is_enabled(), Ok(true));
+    // Enabling the unit once more reports that it was already enabled.
+    assert_eq!(unit.enable(), Ok(true));
+
+    assert_eq!(unit.disable(), Ok(false));
+    assert_eq!(systemd.unit("enable-cronie.service").unwrap().file_state, "disabled");
+    assert_eq!(unit.disable(), Ok(true));
+    assert_eq!(reload_unit(&unit).unwrap().state, UnitState::Disabled);
+}
+
+#[test]
+fn test_start_and_stop() {
+    let systemd = mock_systemd!();
+    systemd.add_unit(MockUnit::new("start-nginx.service", "Web Server"));
+    let unit = find_unit(BusType::Session, "start-nginx.service").unwrap();
+    assert_eq!(reload_unit(&unit).unwrap().state_change, None);
+
+    assert_eq!(unit.start(), Ok(()));
+    let reloaded = reload_unit(&unit).unwrap();
+    assert_eq!(reloaded.active_state, ActiveState::Active);
+    assert_eq!(reloaded.sub_state, "running");
+    assert_eq!(reloaded.description, "Web Server");
+    assert!(reloaded.state_change.is_some());
+    assert_eq!(reloaded.can_reload, Some(true));
+
+    assert_eq!(unit.stop(), Ok(()));
+    let reloaded = reload_unit(&unit).unwrap();
+    assert_eq!(reloaded.active_state, ActiveState::Inactive);
+    assert_eq!(reloaded.sub_state, "dead");
+}
+
+#[test]
+fn test_errors() {
+    let systemd = mock_systemd!();
+    systemd.add_unit(MockUnit::new("error-docker.service", "Container Engine"));
+    systemd.fail("StartUnit", "error-docker.service", "org.freedesktop.DBus.Error.AccessDenied", "Access denied");
+    systemd.fail("EnableUnitFiles", "error-docker.service", "org.freedesktop.DBus.Error.NoReply", "No reply");
+    let unit = find_unit(BusType::Session, "error-docker.service").unwrap();
+
+    assert_eq!(unit.start(), Err(Error::AccessDenied(String::from("Access denied"))));
+    assert_eq!(unit.enable(), Err(Error::Timeout));
+    assert_eq!(systemd.unit("error-docker.service").unwrap().active_state, "inactive");
+
+    // Units that have since been removed can no longer be loaded.
+    let mut removed = unit.clone();
+    removed.name = String::from("error-removed.service");
+    match reload_unit(&removed) {
+        Err(Error::NoSuchUnit(_)) => (),
+        result => panic!("expected the unit to be missing, but found {:?}", result),
+    }
+}
+
+#[test]
+fn test_address_backend() {
+    let systemd = mock_systemd!();
+    systemd.add_unit(MockUnit::new("address-redis.service", "Key-Value Store").enabled());
+    let backend = AddressBackend::new(&env::var("DBUS_SESSION_BUS_ADDRESS").unwrap(), BusType::System);
+
+    let unit = backend.list_unit_files().unwrap().into_iter()
+        .find(|unit| unit.name == "address-redis.service")
+        .unwrap();
+    assert_eq!(unit.bustype, BusType::System);
+    assert_eq!(backend.start(&unit), Ok(()));
+    assert_eq!(backend.reload_unit(&unit).unwrap().active_state, ActiveState::Active);
+    assert_eq!(backend.disable(&unit), Ok(false));
+    assert_eq!(systemd.unit("address-redis.service").unwrap().file_state, "disabled");
+    assert_eq!(backend.stop(&unit), Ok(()));
+    assert_eq!(backend.restart(&unit), Ok(()));
+    assert_eq!(backend.reload_unit(&unit).unwrap().sub_state, "running");
+    assert_eq!(backend.reload(&unit), Ok(()));
+    assert_eq!(backend.mask(&unit), Ok(false));
+    assert_eq!(backend.mask(&unit), Ok(true));
+    assert_eq!(backend.reload_unit(&unit).unwrap().state, UnitState::Masked);
+    // Neither the unit file nor the journal can be accessed through the bus.
+    assert!(backend.unit_file(&unit).is_err());
+    assert!(backend.save_unit_file(&unit, "[Unit]\n").is_err());
+    assert!(backend.journal(&unit.journal_query()).is_err());
+    assert!(backend.journal_query(&unit).is_err());
+}
+
+#[test]
+fn test_watch() {
+    let systemd = mock_systemd!();
+    systemd.add_unit(MockUnit::new("watch-httpd.service", "Web Server"));
+    let unit = find_unit(BusType::Session, "watch-httpd.service").unwrap();
+
+    let (sender, receiver) = mpsc::channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let watcher = {
+        let stop = stop.clone();
+        thread::spawn(move || LocalBackend::new(BusType::Session).watch(sender, &stop))
+    };
+
+    // The watcher subscribes in the background, so the unit is started until its change is reported.
+    let changed = (BusType::Session, String::from("watch-httpd.service"));
+    let reported = (0..50).any(|_| {
+        assert_eq!(unit.start(), Ok(()));
+        thread::sleep(Duration::from_millis(100));
+        receiver.try_iter().any(|unit| unit == changed)
+    });
+    assert!(reported);
+
+    stop.store(true, Ordering::Relaxed);
+    assert_eq!(watcher.join().unwrap(), Ok(()));
+}
@@ -0,0 +1,382 @@
+//! A fake systemd manager, `org.freedesktop.systemd1`, which is registered on a private `dbus-daemon` so that the
+//! backend's D-Bus code can be tested without root or a running systemd. The units that it manages are scripted by
+//! each test, as are the errors that it replies with.
+//!
+//! The daemon is launched once for the whole test binary, and the library reaches it as the session bus. Tests
+//! therefore share the manager, and each test gives its units names of its own.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Write};
+use std::mem;
+use std::process::{self, Command, Stdio};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use systemd_manager::systemd::dbus::dbus::{BusType, Connection, ConnectionItem, Message, MessageItem, NameFlag};
+
+const MANAGER_PATH: &str = "/org/freedesktop/systemd1";
+const MANAGER_INTERFACE: &str = "org.freedesktop.systemd1.Manager";
+const PROPERTIES_INTERFACE: &str = "org.freedesktop.DBus.Properties";
+
+/// Allows the test process to connect to the bus and to own any name.
+const CONFIG: &str = r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:tmpdir=/tmp</listen>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#;
+
+/// A unit that is managed by the fake manager.
+#[derive(Clone, Debug)]
+pub struct MockUnit {
+    pub name: String,
+    pub path: String,
+    pub description: String,
+    pub file_state: String,
+    pub active_state: String,
+    pub sub_state: String,
+    /// The time of the last state change in microseconds since the Unix epoch, which is zero until it is started or
+    /// stopped.
+    pub state_change: u64,
+}
+
+impl MockUnit {
+    /// A unit file in `/etc/systemd/system` that is disabled and not running.
+    pub fn new(name: &str, description: &str) -> MockUnit {
+        MockUnit {
+            name: name.to_owned(),
+            path: format!("/etc/systemd/system/{}", name),
+            description: description.to_owned(),
+            file_state: String::from("disabled"),
+            active_state: String::from("inactive"),
+            sub_state: String::from("dead"),
+            state_change: 0,
+        }
+    }
+
+    pub fn enabled(mut self) -> MockUnit {
+        self.file_state = String::from("enabled");
+        self
+    }
+
+    pub fn running(mut self) -> MockUnit {
+        self.active_state = String::from("active");
+        self.sub_state = String::from("running");
+        self
+    }
+}
+
+/// The scripted state of the fake manager.
+#[derive(Default)]
+pub struct MockSystemd {
+    units: Mutex<BTreeMap<String, MockUnit>>,
+    /// The errors to reply with, keyed by the method and the name of the unit that it is called with.
+    errors: Mutex<HashMap<(String, String), (String, String)>>,
+}
+
+impl MockSystemd {
+    /// Adds the unit to the manager, replacing any unit with the same name.
+    pub fn add_unit(&self, unit: MockUnit) {
+        self.units.lock().unwrap().insert(unit.name.clone(), unit);
+    }
+
+    /// Obtains the current state of the unit with the given name.
+    pub fn unit(&self, name: &str) -> Option<MockUnit> {
+        self.units.lock().unwrap().get(name).cloned()
+    }
+
+    /// Replies to every call of the method upon the given unit with the named D-Bus error.
+    pub fn fail(&self, method: &str, unit: &str, error: &str, message: &str) {
+        self.errors.lock().unwrap()
+            .insert((method.to_owned(), unit.to_owned()), (error.to_owned(), message.to_owned()));
+    }
+}
+
+/// Launches the private bus and registers the fake manager upon it, the first time that it is called. Returns
+/// `None` if `dbus-daemon` is not installed, in which case the calling test should be skipped.
+pub fn systemd() -> Option<Arc<MockSystemd>> {
+    static SYSTEMD: OnceLock<Option<Arc<MockSystemd>>> = OnceLock::new();
+    let systemd = SYSTEMD.get_or_init(|| {
+        let address = match launch_daemon() {
+            Some(address) => address,
+            None => {
+                eprintln!("dbus-daemon is not available, so the D-Bus tests are skipped");
+                return None;
+            }
+        };
+        // The library connects to this bus whenever it is asked for the session bus.
+        env::set_var("DBUS_SESSION_BUS_ADDRESS", &address);
+
+        let systemd = Arc::new(MockSystemd::default());
+        let (ready, wait) = mpsc::channel();
+        let state = systemd.clone();
+        thread::spawn(move || serve(&state, ready));
+        wait.recv().expect("the fake systemd manager was unable to register itself");
+        Some(systemd)
+    });
+    systemd.clone()
+}
+
+/// Launches `dbus-daemon` with a configuration of its own, and returns the address of the bus. The daemon is
+/// launched through a shell that waits on the standard input, which is closed when the test process exits, so that
+/// the daemon is killed along with the tests.
+fn launch_daemon() -> Option<String> {
+    let config = env::temp_dir().join(format!("systemd-manager-test-{}.conf", process::id()));
+    File::create(&config).and_then(|mut file| file.write_all(CONFIG.as_bytes())).ok()?;
+
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg("command -v dbus-daemon >/dev/null || exit 1; \
+              dbus-daemon --config-file=\"$0\" --print-address --nofork & read _; kill $!")
+        .arg(&config)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+
+    let mut address = String::new();
+    let read = BufReader::new(child.stdout?).read_line(&mut address);
+    let _ = fs::remove_file(&config);
+    // The standard input is kept open for as long as the test process is running.
+    mem::forget(child.stdin);
+    match read {
+        Ok(_) if !address.trim().is_empty() => Some(address.trim().to_owned()),
+        _ => None,
+    }
+}
+
+/// Owns the name of the systemd manager on the private bus, and replies to each call that is made to it.
+fn serve(systemd: &MockSystemd, ready: mpsc::Sender<()>) {
+    let connection = Connection::get_private(BusType::Session).unwrap();
+    connection.register_name("org.freedesktop.systemd1", NameFlag::ReplaceExisting as u32).unwrap();
+    connection.register_object_path(MANAGER_PATH).unwrap();
+    ready.send(()).unwrap();
+
+    // The object paths of units are registered as they are loaded.
+    let mut registered = HashSet::new();
+    for item in connection.iter(100) {
+        if let ConnectionItem::MethodCall(message) = item {
+            let (reply, path) = reply(systemd, &message);
+            if let Some(path) = path {
+                if registered.insert(path.clone()) {
+                    connection.register_object_path(&path).unwrap();
+                }
+            }
+            // A job that was queued changes the state of its unit, which is announced to the subscribed clients.
+            let member = message.member().map(|member| member.to_string()).unwrap_or_default();
+            let queued = match reply.get_items().first() {
+                Some(&MessageItem::ObjectPath(_)) => ["StartUnit", "StopUnit", "RestartUnit"].contains(&&member[..]),
+                _ => false,
+            };
+            let _ = connection.send(reply);
+            if let (true, Some(&MessageItem::Str(ref name))) = (queued, message.get_items().first()) {
+                if let Some(unit) = systemd.unit(name) {
+                    let _ = connection.send(properties_changed(&unit));
+                }
+            }
+        }
+    }
+}
+
+/// Performs the call upon the scripted units, and returns the reply along with the object path of a unit that
+/// was loaded by the call.
+fn reply(systemd: &MockSystemd, message: &Message) -> (Message, Option<String>) {
+    let member = message.member().map(|member| member.to_string()).unwrap_or_default();
+    let arguments = message.get_items();
+    let string = |index: usize| match arguments.get(index) {
+        Some(&MessageItem::Str(ref value)) => value.clone(),
+        _ => String::new(),
+    };
+    // The unit files that the enablement methods are called with.
+    let names = || match arguments.get(0) {
+        Some(&MessageItem::Array(ref names, _)) => names.iter()
+            .filter_map(|name| match *name { MessageItem::Str(ref name) => Some(name.clone()), _ => None })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let target = match member.as_str() {
+        "EnableUnitFiles" | "DisableUnitFiles" | "MaskUnitFiles" => names().into_iter().next().unwrap_or_default(),
+        "GetAll" => unit_name(&message.path().map(|path| path.to_string()).unwrap_or_default()),
+        _ => string(0),
+    };
+    if let Some(&(ref error, ref text)) = systemd.errors.lock().unwrap().get(&(member.clone(), target.clone())) {
+        return (Message::new_error(message, error, text).unwrap(), None);
+    }
+
+    let mut units = systemd.units.lock().unwrap();
+    let no_such_unit = |name: &str| {
+        let text = format!("Unit {} not found.", name);
+        (Message::new_error(message, "org.freedesktop.systemd1.NoSuchUnit", &text).unwrap(), None)
+    };
+    let method_return = |items: Vec<MessageItem>| {
+        let mut reply = Message::new_method_return(message).unwrap();
+        reply.append_items(&items);
+        reply
+    };
+
+    match member.as_str() {
+        "ListUnitFiles" => {
+            let files = units.values()
+                .map(|unit| MessageItem::Struct(vec![unit.path.as_str().into(), unit.file_state.as_str().into()]))
+                .collect();
+            (method_return(vec![MessageItem::Array(files, "(ss)".into())]), None)
+        }
+        "ListUnits" => {
+            let loaded = units.values().map(|unit| MessageItem::Struct(vec![
+                unit.name.as_str().into(),
+                unit.description.as_str().into(),
+                "loaded".into(),
+                unit.active_state.as_str().into(),
+                unit.sub_state.as_str().into(),
+                "".into(),
+                MessageItem::ObjectPath(unit_path(&unit.name).into()),
+                MessageItem::UInt32(0),
+                "".into(),
+                MessageItem::ObjectPath("/".into()),
+            ])).collect();
+            (method_return(vec![MessageItem::Array(loaded, "(ssssssouso)".into())]), None)
+        }
+        "GetUnitFileState" => match units.get(&target) {
+            Some(unit) => (method_return(vec![unit.file_state.as_str().into()]), None),
+            None => no_such_unit(&target),
+        },
+        "LoadUnit" => match units.get(&target) {
+            Some(_) => {
+                let path = unit_path(&target);
+                (method_return(vec![MessageItem::ObjectPath(path.clone().into())]), Some(path))
+            }
+            None => no_such_unit(&target),
+        },
+        "GetAll" => match units.get(&target) {
+            Some(unit) => {
+                let property = |name: &str, value: &str| MessageItem::DictEntry(
+                    Box::new(name.into()), Box::new(MessageItem::Variant(Box::new(value.into()))));
+                let properties = vec![
+                    property("Id", &unit.name),
+                    property("Description", &unit.description),
+                    property("FragmentPath", &unit.path),
+                    property("LoadState", "loaded"),
+                    property("ActiveState", &unit.active_state),
+                    property("SubState", &unit.sub_state),
+                    property("UnitFileState", &unit.file_state),
+                    MessageItem::DictEntry(Box::new("StateChangeTimestamp".into()),
+                        Box::new(MessageItem::Variant(Box::new(MessageItem::UInt64(unit.state_change))))),
+                    MessageItem::DictEntry(Box::new("CanReload".into()),
+                        Box::new(MessageItem::Variant(Box::new(MessageItem::Bool(true))))),
+                ];
+                (method_return(vec![MessageItem::Array(properties, "{sv}".into())]), None)
+            }
+            None => no_such_unit(&target),
+        },
+        "StartUnit" | "StopUnit" | "RestartUnit" => match units.get_mut(&target) {
+            Some(unit) => {
+                let start = member != "StopUnit";
+                unit.active_state = String::from(if start { "active" } else { "inactive" });
+                unit.sub_state = String::from(if start { "running" } else { "dead" });
+                unit.state_change = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_micros() as u64;
+                (method_return(vec![MessageItem::ObjectPath("/org/freedesktop/systemd1/job/1".into())]), None)
+            }
+            None => no_such_unit(&target),
+        },
+        // The signals of the units are broadcast to every client, so subscribing to them has no effect.
+        "Subscribe" => (method_return(Vec::new()), None),
+        // Reloading leaves the state of the unit unchanged.
+        "ReloadUnit" => match units.get(&target) {
+            Some(_) => (method_return(vec![MessageItem::ObjectPath("/org/freedesktop/systemd1/job/1".into())]), None),
+            None => no_such_unit(&target),
+        },
+        "EnableUnitFiles" | "DisableUnitFiles" => {
+            let enable = member == "EnableUnitFiles";
+            let mut changes = Vec::new();
+            for name in names() {
+                let unit = match units.get_mut(&name) {
+                    Some(unit) => unit,
+                    None => return no_such_unit(&name),
+                };
+                let link = format!("/etc/systemd/system/multi-user.target.wants/{}", name);
+                if enable && unit.file_state != "enabled" {
+                    unit.file_state = String::from("enabled");
+                    changes.push(MessageItem::Struct(vec!["symlink".into(), link.as_str().into(),
+                        unit.path.as_str().into()]));
+                } else if !enable && unit.file_state == "enabled" {
+                    unit.file_state = String::from("disabled");
+                    changes.push(MessageItem::Struct(vec!["unlink".into(), link.as_str().into(), "".into()]));
+                }
+            }
+            let changes = MessageItem::Array(changes, "(sss)".into());
+            // Only enabling replies with whether the unit files have an `[Install]` section.
+            let items = if enable { vec![MessageItem::Bool(true), changes] } else { vec![changes] };
+            (method_return(items), None)
+        }
+        "MaskUnitFiles" => {
+            let mut changes = Vec::new();
+            for name in names() {
+                let unit = match units.get_mut(&name) {
+                    Some(unit) => unit,
+                    None => return no_such_unit(&name),
+                };
+                if unit.file_state != "masked" {
+                    unit.file_state = String::from("masked");
+                    let link = format!("/etc/systemd/system/{}", name);
+                    changes.push(MessageItem::Struct(vec!["symlink".into(), link.as_str().into(), "/dev/null".into()]));
+                }
+            }
+            (method_return(vec![MessageItem::Array(changes, "(sss)".into())]), None)
+        }
+        _ => {
+            let text = format!("Unknown method {} of {} or {}", member, MANAGER_INTERFACE, PROPERTIES_INTERFACE);
+            (Message::new_error(message, "org.freedesktop.DBus.Error.UnknownMethod", &text).unwrap(), None)
+        }
+    }
+}
+
+/// The `PropertiesChanged` signal that systemd emits when the state of the unit has changed.
+fn properties_changed(unit: &MockUnit) -> Message {
+    let property = |name: &str, value: &str| MessageItem::DictEntry(
+        Box::new(name.into()), Box::new(MessageItem::Variant(Box::new(value.into()))));
+    let mut signal = Message::new_signal(unit_path(&unit.name), PROPERTIES_INTERFACE, "PropertiesChanged").unwrap();
+    signal.append_items(&[
+        "org.freedesktop.systemd1.Unit".into(),
+        MessageItem::Array(vec![
+            property("ActiveState", &unit.active_state),
+            property("SubState", &unit.sub_state),
+        ], "{sv}".into()),
+        MessageItem::Array(Vec::new(), "s".into()),
+    ]);
+    signal
+}
+
+/// The object path of a unit, in which every character that is not alphanumeric is escaped as `_xx`, as systemd does.
+fn unit_path(name: &str) -> String {
+    let escaped: String = name.bytes()
+        .map(|byte| if byte.is_ascii_alphanumeric() { (byte as char).to_string() } else { format!("_{:02x}", byte) })
+        .collect();
+    format!("{}/unit/{}", MANAGER_PATH, escaped)
+}
+
+/// The name of the unit that the object path belongs to.
+fn unit_name(path: &str) -> String {
+    let escaped = path.trim_start_matches(&format!("{}/unit/", MANAGER_PATH)[..]);
+    let mut name = String::new();
+    let mut chars = escaped.chars();
+    while let Some(character) = chars.next() {
+        if character == '_' {
+            let hex: String = chars.by_ref().take(2).collect();
+            name.push(u8::from_str_radix(&hex, 16).unwrap_or(b'_') as char);
+        } else {
+            name.push(character);
+        }
+    }
+    name
+}